@@ -44,6 +44,15 @@ fn load_effects<P: AsRef<Path>>(effects_file: P) -> Vec<Effect> {
     from_reader(file).unwrap()
 }
 
+// Skills refer to their effects using the ids written in the data files
+fn add_effect(effect_builder: &mut IdMapBuilder<Effect>, mut effect: Effect) {
+    effect.convert_negative_dot();
+    let id = effect.id;
+    if effect_builder.insert(id, effect).is_some() {
+        panic!("Effect id {} is defined more than once", id.raw());
+    }
+}
+
 fn load_class<P: AsRef<Path>>(class_file: P) -> Class {
     debug!("load_class from: {:?}", class_file.as_ref());
     let file = fs::File::open(class_file).unwrap();
@@ -68,7 +77,7 @@ pub fn load<P: AsRef<Path>>(dir: P) -> GameDefinition {
         let effects = load_effects(class_dir.join(BUFFS_FILE));
 
         for effect in effects {
-            effect_builder.add(effect);
+            add_effect(&mut effect_builder, effect);
         }
 
         let class_id = class_builder.add(class);
@@ -102,7 +111,7 @@ pub fn load<P: AsRef<Path>>(dir: P) -> GameDefinition {
 
     let common_effects = load_effects(dir.join(BUFFS_FILE));
     for effect in common_effects {
-        effect_builder.add(effect);
+        add_effect(&mut effect_builder, effect);
     }
 
     let maps = load_maps(dir.join(MAPS_DIR)).build();
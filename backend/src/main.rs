@@ -2,10 +2,12 @@
 use log::{debug, info};
 
 use common::{
-    game::{Character, CharacterMapBuilder, GameDefinition, GameMap, GameState, Id},
+    game::{
+        Character, CharacterMapBuilder, GameDefinition, GameMap, GameState, Id, Outcome, Rules,
+    },
     io::{
-        WireAction, WireCreatedChar, WireCreatedGame, WireGetGame, WireNewCharRequest,
-        WireNewGameRequest,
+        WireAction, WireCreatedChar, WireCreatedGame, WireEvents, WireGameView, WireGetGame,
+        WireNewCharRequest, WireNewGameRequest,
     },
 };
 use lazy_static::lazy_static;
@@ -40,6 +42,7 @@ struct ServerBuiltGame {
     character_map_builder: CharacterMapBuilder<'static>,
     map: Id<GameMap>,
     team_size: usize,
+    rules: Rules,
 }
 
 #[post("/new_game", data = "<new_game>")]
@@ -62,9 +65,11 @@ fn create_game(
                 &GAME,
                 new_game.map,
                 new_game.team_size,
+                &new_game.rules,
             ),
             map: new_game.map,
             team_size: new_game.team_size,
+            rules: new_game.rules.clone(),
         },
     );
     Json(WireCreatedGame {
@@ -79,6 +84,27 @@ fn game_state(
     games: State<ServerRunningGames>,
     builders: State<ServerBuiltGames>,
     game: String,
+) -> Json<WireGetGame> {
+    get_game(games, builders, game, None)
+}
+
+#[get("/<game>/<login>")]
+fn game_state_for(
+    games: State<ServerRunningGames>,
+    builders: State<ServerBuiltGames>,
+    game: String,
+    login: String,
+) -> Json<WireGetGame> {
+    get_game(games, builders, game, Some(login))
+}
+
+/// Running games are filtered according to what the team of the player knows (nothing without
+/// a valid login), so that the hidden information cannot be read from the responses
+fn get_game(
+    games: State<ServerRunningGames>,
+    builders: State<ServerBuiltGames>,
+    game: String,
+    login: Option<String>,
 ) -> Json<WireGetGame> {
     let builders = builders.lock().unwrap();
     if let Some(builder) = builders.get(&game) {
@@ -92,8 +118,21 @@ fn game_state(
 
     let games = games.lock().unwrap();
     if let Some(running_game) = games.get(&game) {
-        info!("Found running game for id {game}");
-        return Json(WireGetGame::Running(running_game.game_state.clone()));
+        let game_state = &running_game.game_state;
+        if game_state.outcome() == Outcome::Ongoing {
+            info!("Found running game for id {game}");
+            let team = login
+                .and_then(|login| running_game.login_to_character_id.get(&login))
+                .and_then(|id| game_state.characters.get(*id))
+                .map(|character| character.team);
+            return Json(WireGetGame::Running(WireGameView {
+                game_state: game_state.view_for(&GAME, team),
+                outcome: game_state.outcome(),
+            }));
+        } else {
+            info!("Found finished game for id {game}");
+            return Json(WireGetGame::Finished(game_state.clone()));
+        }
     }
 
     debug!("Games being built: {:?}", *builders);
@@ -123,8 +162,11 @@ fn create_character(
         position: req.position,
         current_health: class.health,
         current_mana: class.mana,
+        shield: 0,
         buffs: Default::default(),
         name: req.name,
+        summoned: None,
+        escort: req.escort,
     };
     // TODO many things...
     let character_id = builder.character_map_builder.add(c).map_err(|_| ())?;
@@ -147,11 +189,12 @@ fn create_character(
         let character_map = builder.character_map_builder.build();
         let login_to_character_id = builder.login_to_character_id;
         let map = builder.map;
+        let seed = rand::thread_rng().gen();
         games.lock().unwrap().insert(
             game,
             ServerRunningGame {
                 login_to_character_id,
-                game_state: GameState::new(&GAME, character_map, map),
+                game_state: GameState::new(&GAME, character_map, map, builder.rules, seed),
             },
         );
     }
@@ -165,13 +208,18 @@ fn character_action(
     game: String,
     login: String,
     action: Json<WireAction>,
-) -> Result<(), ()> {
+) -> Result<Json<WireEvents>, ()> {
     let mut games = games.lock().unwrap();
     // TODO wrong game id
     let game = games.get_mut(&game).ok_or(())?;
     // TODO invalid player
     let character_id = game.login_to_character_id.get(&login).ok_or(())?;
 
+    if game.game_state.outcome() != Outcome::Ongoing {
+        // TODO game is over
+        return Err(());
+    }
+
     let curr_char_id = game.game_state.player_to_play();
 
     if curr_char_id != *character_id {
@@ -180,12 +228,20 @@ fn character_action(
     }
 
     // TODO
-    game.game_state
+    let events = game
+        .game_state
         .next_action(&GAME, (action.0).0)
         .map_err(|_| ())?;
 
-    // TODO
-    Ok(())
+    // the player only learns what their team can see
+    let team = game
+        .game_state
+        .characters
+        .get(*character_id)
+        .expect("Invalid character id")
+        .team;
+    let events = game.game_state.visible_events(&GAME, Some(team), events);
+    Ok(Json(WireEvents(events)))
 }
 
 #[get("/game")]
@@ -212,7 +268,8 @@ fn main() {
                 create_character,
                 character_action,
                 load_game,
-                game_state
+                game_state,
+                game_state_for
             ],
         )
         .launch();
@@ -0,0 +1,74 @@
+use crate::game::character::{Character, Stats};
+use crate::game::effect::{Effect, Range, Target};
+use crate::game::id_map::Id;
+use crate::game::map::Team;
+use serde::{Deserialize, Serialize};
+
+/// When a cell effect applies its effects to a character
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    // the character starts their turn on the cell
+    TurnStart,
+    // the character walks (or is displaced) onto the cell, including the cells crossed on the way
+    Enter,
+    // the character walks (or is displaced) away from the cell, including the cells crossed on
+    // the way
+    Leave,
+}
+
+/// Effect left on the ground e.g. a fire field, a trap or a healing circle
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CellEffect {
+    /// Cells covered around the target cell, None means the target cell only
+    pub range: Option<Range>,
+    /// Applied to the characters triggering the cell effect. Only damage, heal and buff effects
+    /// are supported
+    pub effects: Vec<Id<Effect>>,
+    pub triggers: Vec<Trigger>,
+    /// Characters triggering the effects, relative to the team of the caster
+    pub target: Target,
+    /// Number of turns of the caster during which the effect stays on the ground
+    pub duration: u32,
+    /// Hidden cell effects are only visible to the team of the caster e.g. traps
+    #[serde(default)]
+    pub hidden: bool,
+    /// The effect disappears (from every cell it covers) once triggered
+    #[serde(default)]
+    pub single_use: bool,
+}
+
+/// Cell effect on the ground, as stored in the `GameState`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CellEffectInstance {
+    pub effect: Id<Effect>,
+    pub cell_effect: CellEffect,
+    /// Character who created the cell effect
+    pub source: Id<Character>,
+    pub team: Id<Team>,
+    /// Stats of the source when the cell effect was created
+    pub caster_stats: Stats,
+    pub remaining_duration: u32,
+}
+
+impl CellEffectInstance {
+    pub fn new(
+        effect: Id<Effect>,
+        cell_effect: &CellEffect,
+        source: Id<Character>,
+        team: Id<Team>,
+        caster_stats: Stats,
+    ) -> CellEffectInstance {
+        CellEffectInstance {
+            effect,
+            cell_effect: cell_effect.clone(),
+            source,
+            team,
+            caster_stats,
+            remaining_duration: cell_effect.duration,
+        }
+    }
+
+    pub fn is_visible_to(&self, team: Id<Team>) -> bool {
+        !self.cell_effect.hidden || self.team == team
+    }
+}
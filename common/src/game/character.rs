@@ -1,25 +1,43 @@
-use crate::game::class::Class;
-use crate::game::effect::Buff;
+use crate::game::class::{Attribute, Class};
+use crate::game::damage::DamageBreakdown;
+use crate::game::effect::{Buff, BuffKind, Effect, ShieldKind, Stacking, Status, ValueKind};
 use crate::game::error::Error;
 use crate::game::game_definition::GameDefinition;
+use crate::game::game_state::Rules;
+use crate::game::heal::Overheal;
 use crate::game::id_map::{Id, IdMap, IdMapBuilder};
 use crate::game::map::Cell;
 use crate::game::map::{GameMap, Team};
+use crate::game::mode::GameMode;
+use crate::game::summon::{Behaviour, Summoned};
 use serde::{Deserialize, Serialize};
 
+// Mana regenerated at the start of each turn, per point of concentration
+const MANA_REGEN_PER_CONCENTRATION: f32 = 0.1;
+
 #[derive(Debug)]
 pub struct CharacterMapBuilder<'a> {
     builder: IdMapBuilder<Character>,
     empty_starting_cells: Vec<(usize, Vec<Id<Cell>>)>,
     game_definition: &'a GameDefinition,
+    /// Team which needs an escort, with the `Escort` mode
+    escorted_team: Option<Id<Team>>,
+    has_escort: bool,
 }
 
 impl<'a> CharacterMapBuilder<'a> {
-    pub fn new(game_definition: &'a GameDefinition, map_id: Id<GameMap>, team_size: usize) -> Self {
-        let empty_starting_cells = game_definition
-            .maps
-            .get(map_id)
-            .expect("Invalid map id")
+    pub fn new(
+        game_definition: &'a GameDefinition,
+        map_id: Id<GameMap>,
+        team_size: usize,
+        rules: &Rules,
+    ) -> Self {
+        let map = game_definition.maps.get(map_id).expect("Invalid map id");
+        let escorted_team = match rules.mode_on(map) {
+            GameMode::Escort { team } => Some(team),
+            _ => None,
+        };
+        let empty_starting_cells = map
             .teams
             .iter()
             .map(|team| (team_size, team.1.clone()))
@@ -29,6 +47,8 @@ impl<'a> CharacterMapBuilder<'a> {
             builder: IdMapBuilder::new(),
             empty_starting_cells,
             game_definition,
+            escorted_team,
+            has_escort: false,
         }
     }
 
@@ -38,8 +58,9 @@ impl<'a> CharacterMapBuilder<'a> {
             .get_mut(c.team.raw())
             .expect("Wrong team id was assigned to the character");
 
-        if self.game_definition.classes.get(c.class).is_none() {
-            return Err(Error::InvalidCharacterClass);
+        match self.game_definition.classes.get(c.class) {
+            Some(class) if !class.summon => {}
+            _ => return Err(Error::InvalidCharacterClass),
         }
 
         if c.name.trim().is_empty() {
@@ -51,6 +72,20 @@ impl<'a> CharacterMapBuilder<'a> {
             return Err(Error::TeamFull);
         }
 
+        // The escorted team has exactly one escort, and no other team has any
+        match self.escorted_team {
+            Some(team) if team == c.team => {
+                if c.escort && self.has_escort {
+                    return Err(Error::InvalidEscort);
+                }
+                if !c.escort && !self.has_escort && *spots_left == 1 {
+                    return Err(Error::MissingEscort);
+                }
+            }
+            _ if c.escort => return Err(Error::InvalidEscort),
+            _ => {}
+        }
+
         // Available cells left
         let index = cells_left.iter().position(|cell| *cell == c.position);
         match index {
@@ -61,6 +96,7 @@ impl<'a> CharacterMapBuilder<'a> {
         }
 
         *spots_left -= 1;
+        self.has_escort |= c.escort;
         Ok(self.builder.add(c))
     }
 
@@ -90,9 +126,71 @@ impl<'a> CharacterMapBuilder<'a> {
     }
 }
 
-// TODO: store the applier of the buff's stats here!
+/// Effective stats of a character at a given point in time
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub health: i32,
+    pub mana: i32,
+    pub swiftness: i32,
+
+    pub strength: i32,
+    pub dexterity: i32,
+    pub armor: i32,
+
+    pub intelligence: i32,
+    pub concentration: i32,
+    pub willpower: i32,
+
+    pub vision: i32,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct BuffInstance(Buff, ());
+pub struct BuffInstance {
+    pub effect: Id<Effect>,
+    pub buff: Buff,
+    /// Character who applied the buff
+    pub source: Id<Character>,
+    /// Stats of the source when the buff was applied
+    pub caster_stats: Stats,
+    pub cast_turn: u32,
+    pub remaining_duration: i32,
+    /// Damage the buff can still absorb, for shields
+    #[serde(default)]
+    pub remaining_pool: i32,
+}
+
+/// Damage absorbed by a shield buff
+#[derive(Debug, Clone)]
+pub struct Absorption {
+    pub source: Id<Character>,
+    pub effect: Id<Effect>,
+    pub amount: i32,
+    /// The shield was removed because its pool is empty
+    pub depleted: bool,
+}
+
+impl BuffInstance {
+    pub fn new(
+        effect: Id<Effect>,
+        buff: &Buff,
+        source: Id<Character>,
+        caster_stats: Stats,
+        cast_turn: u32,
+    ) -> BuffInstance {
+        BuffInstance {
+            effect,
+            buff: buff.clone(),
+            source,
+            caster_stats,
+            cast_turn,
+            remaining_duration: buff.duration,
+            remaining_pool: match &buff.kind {
+                BuffKind::Shield(shield) => shield.compute_pool(&caster_stats),
+                _ => 0,
+            },
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Character {
@@ -101,9 +199,18 @@ pub struct Character {
 
     pub current_health: i32,
     pub current_mana: i32,
+    /// Damage absorbed before the health is reduced, built up by overhealing
+    #[serde(default)]
+    pub shield: i32,
     pub position: Id<Cell>,
     pub buffs: Vec<BuffInstance>,
     pub team: Id<Team>,
+    /// Set for the units spawned by a skill
+    #[serde(default)]
+    pub summoned: Option<Summoned>,
+    /// Character the team must bring to an exit, with the `Escort` mode
+    #[serde(default)]
+    pub escort: bool,
 }
 
 impl Character {
@@ -119,48 +226,223 @@ impl Character {
             class: id,
             current_health: class.health,
             current_mana: class.mana,
+            shield: 0,
             position,
             buffs: Vec::new(),
             team,
+            summoned: None,
+            escort: false,
+        }
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.current_health > 0
+    }
+
+    /// Inert summons never play
+    pub fn has_turns(&self) -> bool {
+        !matches!(
+            self.summoned,
+            Some(Summoned {
+                behaviour: Behaviour::Inert,
+                ..
+            })
+        )
+    }
+
+    /// Applies the buff following its stacking rule; buffs are identified by their effect id.
+    /// Returns false if the buff was discarded.
+    pub fn add_buff(&mut self, instance: BuffInstance) -> bool {
+        let existing = self.buffs.iter_mut().find(|b| b.effect == instance.effect);
+        match (existing, instance.buff.stacking) {
+            (Some(existing), Stacking::Refresh) => *existing = instance,
+            (Some(_), Stacking::Ignore) => return false,
+            (Some(_), Stacking::Stack) | (None, _) => self.buffs.push(instance),
+        }
+        true
+    }
+
+    /// Restores health up to the effective health of the character. Returns the health actually
+    /// restored, and the shield gained from the excess (if any).
+    pub fn heal(&mut self, class: &Class, amount: i32, overheal: Overheal) -> (i32, i32) {
+        let max_health = self.effective_health(class);
+        let healed = std::cmp::max(0, std::cmp::min(amount, max_health - self.current_health));
+        self.current_health += healed;
+
+        let shield = match overheal {
+            Overheal::Discard => 0,
+            // the shield can never exceed the maximum health of the character
+            Overheal::Shield => std::cmp::min(amount - healed, max_health - self.shield).max(0),
+        };
+        self.shield += shield;
+        (healed, shield)
+    }
+
+    /// Makes sure the current health does not exceed the effective health e.g. after a health buff
+    /// expired
+    pub fn clamp_health(&mut self, class: &Class) {
+        self.current_health = std::cmp::min(self.current_health, self.effective_health(class));
+    }
+
+    /// Mana regenerates at the start of each turn depending on the concentration, up to the
+    /// effective mana of the character
+    pub fn regenerate_mana(&mut self, class: &Class) {
+        let regen =
+            (self.effective_concentration(class) as f32 * MANA_REGEN_PER_CONCENTRATION) as i32;
+        let max_mana = self.effective_mana(class);
+        if self.current_mana < max_mana {
+            self.current_mana = std::cmp::min(max_mana, self.current_mana + regen);
+        }
+    }
+
+    /// Decrements the duration of every buff (except the statuses), and removes the expired ones
+    pub fn tick_buffs(&mut self) -> Vec<BuffInstance> {
+        self.tick_buffs_matching(|kind| !matches!(kind, BuffKind::Status(_)))
+    }
+
+    /// Same as `tick_buffs` for the statuses, which are ticked at the end of the turn
+    pub fn tick_statuses(&mut self) -> Vec<BuffInstance> {
+        self.tick_buffs_matching(|kind| matches!(kind, BuffKind::Status(_)))
+    }
+
+    fn tick_buffs_matching(&mut self, filter: impl Fn(&BuffKind) -> bool) -> Vec<BuffInstance> {
+        for buff in &mut self.buffs {
+            if filter(&buff.buff.kind) {
+                buff.remaining_duration -= 1;
+            }
+        }
+        let (expired, active) = self
+            .buffs
+            .drain(..)
+            .partition(|b| b.remaining_duration <= 0);
+        self.buffs = active;
+        expired
+    }
+
+    /// Absorbs the damage with the shield buffs (oldest first), then the overheal shield. Returns
+    /// the damage left to be taken, and what each shield buff absorbed
+    pub fn absorb_damage(&mut self, damage: &DamageBreakdown) -> (i32, Vec<Absorption>) {
+        let (mut physical, mut magical, mut pure) = (damage.physical, damage.magical, damage.pure);
+        let mut absorptions = Vec::new();
+
+        for instance in &mut self.buffs {
+            let kind = match &instance.buff.kind {
+                BuffKind::Shield(shield) if instance.remaining_pool > 0 => shield.kind,
+                _ => continue,
+            };
+            let before = instance.remaining_pool;
+            // for shields absorbing everything, physical damage is absorbed first, then magical
+            // and pure damage
+            let components: &mut [&mut i32] = match kind {
+                ShieldKind::All => &mut [&mut physical, &mut magical, &mut pure],
+                ShieldKind::Physical => &mut [&mut physical],
+                ShieldKind::Magical => &mut [&mut magical],
+            };
+            for component in components.iter_mut() {
+                let absorbed = std::cmp::min(instance.remaining_pool, **component);
+                **component -= absorbed;
+                instance.remaining_pool -= absorbed;
+            }
+
+            if instance.remaining_pool < before {
+                absorptions.push(Absorption {
+                    source: instance.source,
+                    effect: instance.effect,
+                    amount: before - instance.remaining_pool,
+                    depleted: instance.remaining_pool == 0,
+                });
+            }
+        }
+        self.buffs.retain(|instance| {
+            !matches!(instance.buff.kind, BuffKind::Shield(_)) || instance.remaining_pool > 0
+        });
+
+        let left = physical + magical + pure;
+        let absorbed = std::cmp::min(self.shield, left);
+        self.shield -= absorbed;
+        (left - absorbed, absorptions)
+    }
+
+    pub fn has_status(&self, status: Status) -> bool {
+        self.buffs
+            .iter()
+            .any(|instance| matches!(instance.buff.kind, BuffKind::Status(s) if s == status))
+    }
+
+    /// Flat modifiers are summed and added to the base value first, then the sum of the
+    /// percentage modifiers is applied e.g. +100 and +50% on a base of 1000 gives 1650
+    fn effective_attribute(&self, attribute: Attribute, base: i32) -> i32 {
+        let (flat, perc) =
+            self.buffs.iter().fold((0, 0.0), |(flat, perc), instance| {
+                match &instance.buff.kind {
+                    BuffKind::Attribute(a, ValueKind::Flat(value)) if *a == attribute => {
+                        (flat + value, perc)
+                    }
+                    BuffKind::Attribute(a, ValueKind::Perc(value)) if *a == attribute => {
+                        (flat, perc + value)
+                    }
+                    _ => (flat, perc),
+                }
+            });
+
+        // stats can be lowered down to 0, but never below
+        std::cmp::max(0, ((base + flat) as f32 * (1.0 + perc)) as i32)
+    }
+
+    pub fn effective_stats(&self, class: &Class) -> Stats {
+        Stats {
+            health: self.effective_health(class),
+            mana: self.effective_mana(class),
+            swiftness: self.effective_swiftness(class),
+            strength: self.effective_strength(class),
+            dexterity: self.effective_dexterity(class),
+            armor: self.effective_armor(class),
+            intelligence: self.effective_intelligence(class),
+            concentration: self.effective_concentration(class),
+            willpower: self.effective_willpower(class),
+            vision: self.effective_vision(class),
         }
     }
 
-    // TODO: look at current debuffs to return the "effective" stats
     pub fn effective_health(&self, class: &Class) -> i32 {
-        class.health
+        self.effective_attribute(Attribute::Health, class.health)
     }
 
     pub fn effective_mana(&self, class: &Class) -> i32 {
-        class.mana
+        self.effective_attribute(Attribute::Mana, class.mana)
     }
 
     pub fn effective_swiftness(&self, class: &Class) -> i32 {
-        class.swiftness
+        self.effective_attribute(Attribute::Swiftness, class.swiftness)
     }
 
     // PHYSICAL STUFF
     pub fn effective_strength(&self, class: &Class) -> i32 {
-        class.strength
+        self.effective_attribute(Attribute::Strength, class.strength)
     }
 
     pub fn effective_dexterity(&self, class: &Class) -> i32 {
-        class.dexterity
+        self.effective_attribute(Attribute::Dexterity, class.dexterity)
     }
 
     pub fn effective_armor(&self, class: &Class) -> i32 {
-        class.armor
+        self.effective_attribute(Attribute::Armor, class.armor)
     }
 
     // MAGICAL STUFF
     pub fn effective_intelligence(&self, class: &Class) -> i32 {
-        class.intelligence
+        self.effective_attribute(Attribute::Intelligence, class.intelligence)
     }
 
     pub fn effective_concentration(&self, class: &Class) -> i32 {
-        class.concentration
+        self.effective_attribute(Attribute::Concentration, class.concentration)
     }
 
     pub fn effective_willpower(&self, class: &Class) -> i32 {
-        class.willpower
+        self.effective_attribute(Attribute::Willpower, class.willpower)
+    }
+
+    pub fn effective_vision(&self, class: &Class) -> i32 {
+        self.effective_attribute(Attribute::Vision, class.vision)
     }
 }
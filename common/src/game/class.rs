@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
 
 // NOTE: we could generate the "Class" struct and the "Attribute" enum jonitly with e.g. a macro
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Attribute {
     Health,
+    Mana,
     Swiftness,
     Strength,
     Dexterity,
@@ -11,6 +12,11 @@ pub enum Attribute {
     Intelligence,
     Concentration,
     Willpower,
+    Vision,
+}
+
+fn default_vision() -> i32 {
+    8
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -32,4 +38,12 @@ pub struct Class {
     pub intelligence: i32,  // dmg
     pub concentration: i32, // mana (precision for spell is always 100%)
     pub willpower: i32,     // spell defense
+
+    /// Number of cells (L1 distance) the character can see, provided they are in line of sight
+    #[serde(default = "default_vision")]
+    pub vision: i32,
+
+    /// Stat block of a summoned unit, which cannot be picked by the players
+    #[serde(default)]
+    pub summon: bool,
 }
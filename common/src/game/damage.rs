@@ -1,9 +1,14 @@
-use crate::game::character::Character;
-use crate::game::class::Class;
-use crate::game::id_map::IdMap;
+use crate::game::character::Stats;
+use crate::game::heal::Heal;
 use serde::{Deserialize, Serialize};
+use std::ops::AddAssign;
+
+/// Armor (resp. willpower) needed to halve the physical (resp. magical) damage taken.
+/// Mitigation follows `stat / (stat + MITIGATION_SCALE)` so it never reaches 100%.
+const MITIGATION_SCALE: f32 = 100.0;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
 pub struct Damage {
     #[serde(default)]
     flat_magical: i32,
@@ -19,36 +24,183 @@ pub struct Damage {
     #[serde(default)]
     perc_pure: f32,
 
+    /// Scales with the attacker's intelligence
     #[serde(default)]
     perc_modif_magical: f32,
+    /// Scales with the attacker's strength
     #[serde(default)]
     perc_modif_physical: f32,
 }
 
+/// Details of the damage dealt, per type, before and after mitigation
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DamageBreakdown {
+    pub raw_physical: i32,
+    pub raw_magical: i32,
+    /// Physical damage left after the defender's armor
+    pub physical: i32,
+    /// Magical damage left after the defender's willpower
+    pub magical: i32,
+    /// Pure damage is never mitigated
+    pub pure: i32,
+}
+
+impl DamageBreakdown {
+    pub fn raw(&self) -> i32 {
+        self.raw_physical + self.raw_magical + self.pure
+    }
+
+    pub fn mitigated(&self) -> i32 {
+        self.raw() - self.total()
+    }
+
+    pub fn total(&self) -> i32 {
+        self.physical + self.magical + self.pure
+    }
+}
+
+impl AddAssign for DamageBreakdown {
+    fn add_assign(&mut self, other: DamageBreakdown) {
+        self.raw_physical += other.raw_physical;
+        self.raw_magical += other.raw_magical;
+        self.physical += other.physical;
+        self.magical += other.magical;
+        self.pure += other.pure;
+    }
+}
+
+fn mitigate(raw: i32, stat: i32) -> i32 {
+    let stat = stat.max(0) as f32;
+    let reduction = stat / (stat + MITIGATION_SCALE);
+    (raw as f32 * (1.0 - reduction)).round() as i32
+}
+
 impl Damage {
-    pub fn compute_damage(
-        &self,
-        classes: &IdMap<Class>,
-        attacker: &Character,
-        defender: &Character,
-    ) -> i32 {
-        let c_att = classes.get(attacker.class).expect("Invalid attacker class");
-        let c_def = classes.get(defender.class).expect("Invalid attacker class");
-
-        let physical_raw = self.flat_physical
-            + ((self.perc_physical * defender.effective_health(c_def) as f32) as i32)
-            + ((self.perc_modif_physical * attacker.effective_strength(c_att) as f32) as i32);
-
-        let magical_raw = self.flat_magical
-            + ((self.perc_magical * defender.effective_health(c_def) as f32) as i32)
-            + ((self.perc_modif_magical * attacker.effective_strength(c_att) as f32) as i32);
-
-        let pure_raw =
-            self.flat_pure + ((self.perc_pure * defender.effective_health(c_def) as f32) as i32);
-
-        let physical_final = physical_raw - defender.effective_armor(c_def);
-        let magical_final = magical_raw - defender.effective_willpower(c_def);
-
-        physical_final + magical_final + pure_raw
+    /// Whether any of the values is negative i.e. lowers the damage dealt (which never goes
+    /// below 0)
+    pub fn has_negative_values(&self) -> bool {
+        self.flat_magical < 0
+            || self.flat_physical < 0
+            || self.flat_pure < 0
+            || self.perc_magical < 0.0
+            || self.perc_physical < 0.0
+            || self.perc_pure < 0.0
+            || self.perc_modif_magical < 0.0
+            || self.perc_modif_physical < 0.0
+    }
+
+    /// Heal equivalent to a damage made only of negative values, None if any of them is
+    /// positive. The percentages of the health of the target become a percentage of their
+    /// maximum health, and the overheal is discarded.
+    pub fn to_heal(&self) -> Option<Heal> {
+        let flats = [self.flat_magical, self.flat_physical, self.flat_pure];
+        let percs = [self.perc_magical, self.perc_physical, self.perc_pure];
+        if !self.has_negative_values()
+            || flats.iter().any(|value| *value > 0)
+            || percs.iter().any(|value| *value > 0.0)
+            || self.perc_modif_magical > 0.0
+            || self.perc_modif_physical > 0.0
+        {
+            return None;
+        }
+
+        Some(Heal::new(
+            -flats.iter().sum::<i32>(),
+            -percs.iter().sum::<f32>(),
+            -self.perc_modif_magical,
+            -self.perc_modif_physical,
+        ))
+    }
+
+    pub fn compute_damage(&self, attacker: &Stats, defender: &Stats) -> DamageBreakdown {
+        let raw_physical = (self.flat_physical
+            + ((self.perc_physical * defender.health as f32) as i32)
+            + ((self.perc_modif_physical * attacker.strength as f32) as i32))
+            .max(0);
+
+        let raw_magical = (self.flat_magical
+            + ((self.perc_magical * defender.health as f32) as i32)
+            + ((self.perc_modif_magical * attacker.intelligence as f32) as i32))
+            .max(0);
+
+        let pure = (self.flat_pure + ((self.perc_pure * defender.health as f32) as i32)).max(0);
+
+        DamageBreakdown {
+            raw_physical,
+            raw_magical,
+            physical: mitigate(raw_physical, defender.armor),
+            magical: mitigate(raw_magical, defender.willpower),
+            pure,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn stats(strength: i32, intelligence: i32, armor: i32, willpower: i32) -> Stats {
+        Stats {
+            health: 1000,
+            mana: 0,
+            swiftness: 0,
+            strength,
+            dexterity: 0,
+            armor,
+            intelligence,
+            concentration: 0,
+            willpower,
+            vision: 0,
+        }
+    }
+
+    #[test]
+    fn test_scaling() {
+        let damage: Damage = serde_json::from_value(json!({
+            "perc_modif_physical": 1.0,
+            "perc_modif_magical": 2.0,
+        }))
+        .unwrap();
+
+        let breakdown = damage.compute_damage(&stats(10, 20, 0, 0), &stats(0, 0, 0, 0));
+        assert_eq!(breakdown.raw_physical, 10);
+        assert_eq!(breakdown.raw_magical, 40);
+        assert_eq!(breakdown.total(), 50);
+        assert_eq!(breakdown.mitigated(), 0);
+    }
+
+    #[test]
+    fn test_mitigation() {
+        let damage: Damage = serde_json::from_value(json!({
+            "flat_physical": 100,
+            "flat_magical": 100,
+            "flat_pure": 100,
+        }))
+        .unwrap();
+
+        let breakdown = damage.compute_damage(&stats(0, 0, 0, 0), &stats(0, 0, 100, 300));
+        assert_eq!(
+            breakdown,
+            DamageBreakdown {
+                raw_physical: 100,
+                raw_magical: 100,
+                physical: 50,
+                magical: 25,
+                pure: 100,
+            }
+        );
+        assert_eq!(breakdown.raw(), 300);
+        assert_eq!(breakdown.mitigated(), 125);
+
+        // damage can never heal, whatever the stats
+        let breakdown = damage.compute_damage(&stats(0, 0, 0, 0), &stats(0, 0, 100_000, -50));
+        assert!(breakdown.physical >= 0);
+        assert_eq!(breakdown.magical, 100);
+    }
+
+    #[test]
+    fn test_unknown_fields() {
+        assert!(serde_json::from_value::<Damage>(json!({ "perc_modif_phyisical": 1.0 })).is_err());
     }
 }
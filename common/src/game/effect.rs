@@ -1,6 +1,10 @@
+use crate::game::cell_effect::CellEffect;
+use crate::game::character::Stats;
 use crate::game::class::Attribute;
 use crate::game::damage::Damage;
+use crate::game::heal::Heal;
 use crate::game::id_map::Id;
+use crate::game::summon::Summon;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
@@ -11,7 +15,7 @@ pub enum Target {
     Ally,     // can only target allies
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum RangeKind {
     // range is used as a L1 norm e.g. 1 means the four direct squares around the target are
     // affected as well
@@ -23,9 +27,27 @@ pub enum RangeKind {
     // range is used for each x and y axis e.g. 1 means the 8 squared around the target are in the
     // range
     Square,
+
+    // straight line in the facing direction (or in the four main directions when there is none)
+    Line,
+
+    // straight lines in the four diagonal directions
+    Diagonal,
+
+    // widening triangle in the facing direction (or in the four main directions when there is
+    // none) e.g. 2 means 1 cell in front, then 3 cells on the next row
+    Cone,
+
+    // range is used as an euclidean distance, rounded to the nearest cell, giving a rough circle
+    Ring,
+
+    // data defined pattern, as rows of characters where 'X' marks an affected cell. The center of
+    // the pattern is the origin, and the pattern is drawn facing up: it is rotated according to
+    // the facing direction. min and max are ignored.
+    Mask(Vec<String>),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Range {
     // number of cells from initial one e.g. 0 means only on oneself
     pub min: u32,
@@ -38,6 +60,10 @@ pub struct Range {
 
     // does this range needs line of sight (e.g. works through walls...)
     pub needs_los: bool,
+
+    // can characters standing in the way block the line of sight
+    #[serde(default)]
+    pub blocked_by_characters: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -46,24 +72,101 @@ pub enum ValueKind {
     Flat(i32),
 }
 
+/// Crowd control restricting what the affected character can do
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    // the turn is skipped entirely
+    Stun,
+    // cannot move
+    Root,
+    // cannot cast magical skills
+    Silence,
+    // cannot cast physical skills
+    Disarm,
+}
+
+/// Type of damage a shield absorbs
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShieldKind {
+    // any damage, including pure damage
+    #[default]
+    All,
+    Physical,
+    Magical,
+}
+
+/// Pool of damage absorbed before the health of the character is reduced
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Shield {
+    #[serde(default)]
+    flat: i32,
+    /// Scales with the caster's intelligence
+    #[serde(default)]
+    perc_modif_magical: f32,
+    #[serde(default)]
+    pub kind: ShieldKind,
+}
+
+impl Shield {
+    pub fn compute_pool(&self, caster: &Stats) -> i32 {
+        (self.flat + ((self.perc_modif_magical * caster.intelligence as f32) as i32)).max(0)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum BuffKind {
-    // negative values mean healing i.e. HoT
+    // negative values mean healing i.e. HoT, see `Effect::convert_negative_dot`
     DoT(Damage),
+    HoT(Heal),
     // TODO change that
     Attribute(Attribute, ValueKind),
+    /// Unlike other buffs, the duration is the number of turns affected i.e. it ticks down at the
+    /// end of the turn
+    Status(Status),
+    /// Removed once its pool is depleted, even if its duration is not over
+    Shield(Shield),
+}
+
+/// What happens when a buff is applied to a character already affected by it
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Stacking {
+    // the existing instance is replaced i.e. its duration is reset
+    #[default]
+    Refresh,
+    // each application is tracked (and ticks) separately
+    Stack,
+    // the new application is discarded while the buff is still active
+    Ignore,
+}
+
+/// Which stats of the caster are used when a buff is resolved e.g. for the damage of a DoT
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CasterStats {
+    // stats at the time the buff was applied
+    #[default]
+    OnCast,
+    // current stats of the caster (falls back to the ones on cast if the caster is gone)
+    Live,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Buff {
-    name: String,
+    pub name: String,
     /// None means the damage will be applied to the given cell only (i.e. no "explosion" around
     /// the target)
-    range: Option<Range>,
-    success_rate: Option<f32>,
-    duration: i32,
-    kind: BuffKind,
-    // TODO: add initial stats of attacker somewhere here
+    pub range: Option<Range>,
+    pub success_rate: Option<f32>,
+    /// Number of turns of the afflicted character during which the buff stays active
+    pub duration: i32,
+    pub kind: BuffKind,
+    #[serde(default)]
+    pub stacking: Stacking,
+    #[serde(default)]
+    pub caster_stats: CasterStats,
+    /// Only the team of the caster knows about the buff
+    #[serde(default)]
+    pub hidden: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -74,14 +177,118 @@ pub struct DirectDamage {
     pub damage: Damage,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DirectHeal {
+    /// None means only the given cell is healed
+    pub range: Option<Range>,
+    pub heal: Heal,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum DisplacementKind {
+    // moves the target N cells away from the caster i.e. knockback
+    Push(u32),
+    // moves the target N cells towards the caster, stopping next to them
+    Pull(u32),
+    // moves the caster to the target cell, which must be free
+    Teleport,
+    // the caster and the target exchange their positions
+    Swap,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Displacement {
+    /// None means only the character standing on the given cell is moved. Ignored when
+    /// teleporting
+    pub range: Option<Range>,
+    pub kind: DisplacementKind,
+    /// Damage dealt to the target (and to the character they bump into, if any) when they are
+    /// stopped by a wall, another character or the edge of the map
+    #[serde(default)]
+    pub collision_damage: Damage,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum EffectKind {
     DirectDamage(DirectDamage),
+    Heal(DirectHeal),
     Buff(Buff),
+    Displacement(Displacement),
+    Summon(Summon),
+    CellEffect(CellEffect),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Effect {
     pub id: Id<Effect>,
     pub kind: EffectKind,
+    /// Characters the effect applies to, on top of the target of the skill and of the area of
+    /// the effect (if any) e.g. for a skill damaging an enemy and buffing the caster's allies
+    #[serde(default)]
+    pub target: Option<Target>,
+}
+
+impl Effect {
+    /// DoTs made only of negative values are meant to heal: they are turned into the equivalent
+    /// HoTs, as the damage dealt never goes below 0
+    pub fn convert_negative_dot(&mut self) {
+        if let EffectKind::Buff(buff) = &mut self.kind {
+            if let Some(heal) = match &buff.kind {
+                BuffKind::DoT(damage) => damage.to_heal(),
+                _ => None,
+            } {
+                buff.kind = BuffKind::HoT(heal);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_negative_dot() {
+        let effect = |damage: serde_json::Value| {
+            let mut effect = serde_json::from_value::<Effect>(json!({ "id": 0, "kind": { "Buff": {
+                "name": "dot",
+                "duration": 3,
+                "kind": { "DoT": damage },
+            }}}))
+            .unwrap();
+            effect.convert_negative_dot();
+            match effect.kind {
+                EffectKind::Buff(buff) => buff.kind,
+                _ => unreachable!(),
+            }
+        };
+        let stats = Stats {
+            health: 200,
+            mana: 0,
+            swiftness: 0,
+            strength: 0,
+            dexterity: 0,
+            armor: 0,
+            intelligence: 50,
+            concentration: 0,
+            willpower: 0,
+            vision: 0,
+        };
+
+        assert!(matches!(
+            effect(json!({ "flat_pure": 10 })),
+            BuffKind::DoT(_)
+        ));
+        match effect(json!({ "flat_pure": -10, "perc_physical": -0.1, "perc_modif_magical": -1.0 }))
+        {
+            BuffKind::HoT(heal) => assert_eq!(heal.compute_heal(&stats, &stats), 80),
+            kind => panic!("Unexpected buff kind {kind:?}"),
+        }
+        // damage partly offset by negative values stays a DoT
+        assert!(matches!(
+            effect(json!({ "flat_pure": 10, "flat_magical": -5 })),
+            BuffKind::DoT(_)
+        ));
+    }
 }
@@ -5,19 +5,34 @@ pub enum Error {
     // map loading (deserializing)
     InvalidMapSize,
     OverlappingStartingCells,
+    InvalidObjectiveCell,
 
     // character creation (before game starts)
     TeamFull,
     InvalidCharacterName,
     InvalidStartingCell,
     InvalidCharacterClass,
+    InvalidEscort,
+    MissingEscort,
 
     // player action
+    GameOver,
     InvalidSkill,
+    NotEnoughMana,
+    NotEnoughActionPoints,
     AlreadyMoved,
     InvalidTarget,
     InvalidRange,
+    CellOutOfMap,
+    NoLineOfSight,
     MoveCellTooFar,
+    ImpassableCell,
+    CellOccupied,
+    SkillOnCooldown,
+    NoUsesLeft,
+    Rooted,
+    Silenced,
+    Disarmed,
 }
 
 impl Display for Error {
@@ -29,6 +44,7 @@ impl Display for Error {
             Error::OverlappingStartingCells => {
                 f.write_str("Starting cells cannot have duplicates (different teams or not)")
             }
+            Error::InvalidObjectiveCell => f.write_str("Objective cells must be within the map"),
 
             Error::TeamFull => f.write_str("Team is already full"),
             Error::InvalidStartingCell => {
@@ -40,12 +56,30 @@ impl Display for Error {
             Error::InvalidCharacterName => {
                 f.write_str("Character's name cannot be made only of whitespaces")
             }
+            Error::InvalidEscort => {
+                f.write_str("Only one character of the escorted team can be the escort")
+            }
+            Error::MissingEscort => f.write_str("Escorted team needs an escort"),
 
+            Error::GameOver => f.write_str("Game is already over"),
             Error::InvalidSkill => f.write_str("Team is already full"),
+            Error::NotEnoughMana => f.write_str("Character does not have enough mana"),
+            Error::NotEnoughActionPoints => {
+                f.write_str("Character does not have enough action points left")
+            }
             Error::AlreadyMoved => f.write_str("Character has already moved"),
             Error::InvalidTarget => f.write_str("Target is invalid"),
             Error::InvalidRange => f.write_str("Range is invalid"),
+            Error::CellOutOfMap => f.write_str("Cell is outside of the map"),
+            Error::NoLineOfSight => f.write_str("Target is not in line of sight"),
             Error::MoveCellTooFar => f.write_str("Character cannot move that far"),
+            Error::ImpassableCell => f.write_str("Cell cannot be walked on"),
+            Error::CellOccupied => f.write_str("Cell is already occupied"),
+            Error::SkillOnCooldown => f.write_str("Skill is still on cooldown"),
+            Error::NoUsesLeft => f.write_str("Skill cannot be used anymore in this game"),
+            Error::Rooted => f.write_str("Character is rooted and cannot move"),
+            Error::Silenced => f.write_str("Character is silenced and cannot cast magical skills"),
+            Error::Disarmed => f.write_str("Character is disarmed and cannot use physical skills"),
         }
     }
 }
@@ -0,0 +1,121 @@
+use crate::game::character::Character;
+use crate::game::damage::DamageBreakdown;
+use crate::game::effect::Effect;
+use crate::game::game_state::Outcome;
+use crate::game::id_map::Id;
+use crate::game::map::{Cell, Team};
+use crate::game::skill::Skill;
+use serde::{Deserialize, Serialize};
+
+/// Everything that happened during a game, in order. This is the single source of truth for
+/// combat logs, replays etc.
+/// The characters at the origin of `Missed`, `Damaged`, `Absorbed`, `Healed` and `BuffApplied`
+/// are always known in the log, but not to the players who cannot see them: see
+/// `GameState::visible_events`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    RoundStarted(u32),
+    TurnStarted(Id<Character>),
+    TurnEnded(Id<Character>),
+    /// The character is stunned and cannot play this turn
+    TurnSkipped(Id<Character>),
+    /// One cell at a time, so that the cell effects triggered on the way are logged in order
+    Moved {
+        character: Id<Character>,
+        from: Id<Cell>,
+        to: Id<Cell>,
+    },
+    /// Forced movement e.g. pushed by a skill, one cell at a time (teleports and swaps excepted)
+    Displaced {
+        character: Id<Character>,
+        from: Id<Cell>,
+        to: Id<Cell>,
+    },
+    /// A displaced character was stopped by an obstacle on the given cell (possibly outside of
+    /// the map)
+    Collided {
+        character: Id<Character>,
+        obstacle: Option<Id<Cell>>,
+    },
+    SkillCast {
+        caster: Id<Character>,
+        skill: Id<Skill>,
+        target: Id<Cell>,
+    },
+    Missed {
+        caster: Option<Id<Character>>,
+        target: Id<Character>,
+    },
+    Damaged {
+        source: Option<Id<Character>>,
+        target: Id<Character>,
+        /// Damage actually taken i.e. once the shields were depleted
+        amount: i32,
+        absorbed: i32,
+        breakdown: DamageBreakdown,
+    },
+    /// Damage absorbed by a shield buff, reported after the corresponding `Damaged` event
+    Absorbed {
+        /// Character who applied the shield
+        source: Option<Id<Character>>,
+        target: Id<Character>,
+        effect: Id<Effect>,
+        amount: i32,
+    },
+    Healed {
+        source: Option<Id<Character>>,
+        target: Id<Character>,
+        amount: i32,
+        /// Overheal converted into a shield
+        shield: i32,
+    },
+    BuffApplied {
+        source: Option<Id<Character>>,
+        target: Id<Character>,
+        effect: Id<Effect>,
+    },
+    BuffExpired {
+        target: Id<Character>,
+        effect: Id<Effect>,
+    },
+    Died(Id<Character>),
+    Summoned {
+        owner: Id<Character>,
+        summon: Id<Character>,
+        cell: Id<Cell>,
+    },
+    CellEffectCreated {
+        source: Id<Character>,
+        effect: Id<Effect>,
+        cells: Vec<Id<Cell>>,
+    },
+    CellEffectTriggered {
+        cell: Id<Cell>,
+        effect: Id<Effect>,
+        character: Id<Character>,
+    },
+    CellEffectExpired {
+        cell: Id<Cell>,
+        effect: Id<Effect>,
+    },
+    /// A summon reached the end of its lifetime (or its owner died) and was removed from the
+    /// game, from the cell it stood on
+    SummonExpired {
+        summon: Id<Character>,
+        owner: Id<Character>,
+        cell: Id<Cell>,
+    },
+    ZoneCaptured {
+        /// Index of the zone in the objectives of the map
+        zone: usize,
+        team: Id<Team>,
+    },
+    /// Points were scored towards the objectives of the game mode, the score is the new total
+    Scored {
+        team: Id<Team>,
+        score: u32,
+    },
+    /// The escorted character reached an exit
+    ExitReached(Id<Character>),
+    GameOver(Outcome),
+}
@@ -16,3 +16,17 @@ pub struct GameDefinition {
     pub class_to_skills: HashMap<Id<Class>, Vec<Id<Skill>>>,
     pub skill_to_classes: HashMap<Id<Skill>, Vec<Id<Class>>>,
 }
+
+impl GameDefinition {
+    /// Classes the players can pick from i.e. everything but the summons
+    pub fn playable_classes(&self) -> Vec<Id<Class>> {
+        let mut classes = self
+            .classes
+            .iter()
+            .filter(|(_, class)| !class.summon)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        classes.sort_by_key(|id| id.raw());
+        classes
+    }
+}
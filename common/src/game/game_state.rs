@@ -1,17 +1,36 @@
-use crate::game::character::Character;
-use crate::game::effect::{EffectKind, Range, RangeKind, Target};
+use crate::game::cell_effect::{CellEffect, CellEffectInstance, Trigger};
+use crate::game::character::{BuffInstance, Character, Stats};
+use crate::game::damage::DamageBreakdown;
+use crate::game::effect::{
+    BuffKind, CasterStats, Displacement, DisplacementKind, Effect, EffectKind, Range, Status,
+    Target,
+};
 use crate::game::error::Error;
+use crate::game::event::GameEvent;
 use crate::game::game_definition::GameDefinition;
+use crate::game::heal::Overheal;
 use crate::game::id_map::{Id, IdMap};
-use crate::game::map::{Cell, GameMap};
-use crate::game::skill::Skill;
+use crate::game::map::{Cell, GameMap, Occupied, Team};
+use crate::game::mode::{Capture, GameMode};
+use crate::game::rng::Rng;
+use crate::game::skill::{Skill, SkillCooldown, SkillKind};
+use crate::game::summon::{Behaviour, Summon, Summoned};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+/// Initiative a character must accumulate to play a turn. Every tick of the timeline, characters
+/// gain initiative equal to their swiftness, so faster characters play more often.
+const INITIATIVE_THRESHOLD: i64 = 100_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TurnState {
     MoveOrAction,
     ActionOnly,
+    /// Points left to spend during the current turn, see `TurnRules::ActionPoints`
+    Points {
+        action_points: u32,
+        movement_points: u32,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -19,219 +38,3665 @@ pub enum Action {
     Movement(Id<Cell>),
     Skill(Id<Skill>, Id<Cell>),
     Pass,
+    /// Ends the turn, same as passing
+    EndTurn,
+}
+
+/// What a character can do during their turn
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TurnRules {
+    /// One movement, then a single action ending the turn
+    #[default]
+    MoveThenAct,
+    /// Every turn, characters get the given action points spent by skills, and as many movement
+    /// points as their swiftness spent by each cell moved through. They can move and act in any
+    /// order until they end their turn.
+    ActionPoints { action_points: u32 },
+}
+
+/// Rules chosen when creating a game
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Rules {
+    /// Characters can move through cells occupied by their allies (but not stop there)
+    #[serde(default)]
+    pub pass_through_allies: bool,
+    #[serde(default)]
+    pub turns: TurnRules,
+    /// Defaults to the mode of the map, if any, or to a deathmatch
+    #[serde(default)]
+    pub mode: Option<GameMode>,
+}
+
+impl Rules {
+    /// Mode of a game played on the given map
+    pub fn mode_on(&self, map: &GameMap) -> GameMode {
+        self.mode.or(map.mode).unwrap_or_default()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Ongoing,
+    Victory(Id<Team>),
+    Draw,
+}
+
+/// Predicted consequences of a skill on one of the characters it affects
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TargetPreview {
+    pub character: Id<Character>,
+    pub hit_chance: f32,
+    /// Damage from the direct damage effects: the minimum accounts for the skill missing
+    pub min_damage: i32,
+    pub max_damage: i32,
+    /// Details of the maximum damage
+    pub damage: DamageBreakdown,
+    /// Health restored by the heal effects, before being clamped to the maximum health
+    pub heal: i32,
+    /// Buffs which might be applied, with their probability
+    pub buffs: Vec<(Id<Effect>, f32)>,
+}
+
+/// Predicted consequences of an action, computed without modifying the game state
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ActionPreview {
+    pub mana_after: i32,
+    /// Cells reached by the action i.e. the path of a movement, or the areas hit by a skill
+    pub cells: Vec<Id<Cell>>,
+    pub targets: Vec<TargetPreview>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GameState {
     pub characters: IdMap<Character>,
     pub map: Id<GameMap>,
-    pub turn_order: Vec<Id<Character>>,
+    /// Character currently playing, None only before the very first turn
+    pub current: Option<Id<Character>>,
+    /// Progress of each character towards their next turn, see `INITIATIVE_THRESHOLD`
+    pub initiative: HashMap<Id<Character>, i64>,
+    /// Characters who started a turn during the current round
+    pub played_this_round: HashSet<Id<Character>>,
     pub turn_state: TurnState,
+    /// Cooldowns of the skills each character used so far
+    pub cooldowns: HashMap<Id<Character>, HashMap<Id<Skill>, SkillCooldown>>,
+    /// Effects left on the ground, per cell
+    pub cell_effects: HashMap<Id<Cell>, Vec<CellEffectInstance>>,
+    /// Number of rounds started so far. A round ends once every character alive has started a
+    /// turn during it, so the fastest characters may play several times per round.
+    pub turn: u32,
+    pub rng: Rng,
+    pub rules: Rules,
+    pub mode: GameMode,
+    /// Id given to the next summon, so that the ids of the expired (i.e. removed) summons are
+    /// never reused
+    pub next_summon: Id<Character>,
+    /// Points scored by each team towards the objectives of the game mode
+    pub scores: HashMap<Id<Team>, u32>,
+    /// State of each capture zone of the map, with the `CapturePoints` mode
+    pub captures: Vec<Capture>,
+    /// Append-only log of everything that happened since the start of the game
+    pub log: Vec<GameEvent>,
 }
 
 impl GameState {
-    pub fn new(g: &GameDefinition, characters: IdMap<Character>, map: Id<GameMap>) -> GameState {
+    pub fn new(
+        g: &GameDefinition,
+        characters: IdMap<Character>,
+        map: Id<GameMap>,
+        rules: Rules,
+        seed: u64,
+    ) -> GameState {
+        let game_map = g.maps.get(map).expect("Invalid game map id");
+        let mode = rules.mode_on(game_map);
+        let captures = match mode {
+            GameMode::CapturePoints { .. } => {
+                vec![Capture::default(); game_map.objectives.capture_zones.len()]
+            }
+            _ => Vec::new(),
+        };
+        let next_summon = Id::new(
+            characters
+                .iter()
+                .map(|(id, _)| id.raw() + 1)
+                .max()
+                .unwrap_or(0),
+        );
         let mut gs = GameState {
             characters,
             map,
-            turn_order: Vec::new(),
+            current: None,
+            initiative: HashMap::new(),
+            played_this_round: HashSet::new(),
             turn_state: TurnState::MoveOrAction,
+            cooldowns: HashMap::new(),
+            cell_effects: HashMap::new(),
+            turn: 0,
+            rng: Rng::new(seed),
+            rules,
+            mode,
+            next_summon,
+            scores: HashMap::new(),
+            captures,
+            log: Vec::new(),
         };
-        gs.new_turn(g);
+        // the escort may start the game on an exit
+        if let GameMode::Escort { team } = gs.mode {
+            if let Some(escort) = gs.escort(team) {
+                gs.check_exit(g, escort);
+            }
+        }
+        gs.next_turn(g);
         gs
     }
 
-    fn new_turn(&mut self, g: &GameDefinition) {
-        assert!(self.turn_order.is_empty());
+    /// Speed of every character alive, sorted by id so that the timeline is deterministic
+    fn speeds(&self, g: &GameDefinition) -> Vec<(Id<Character>, i64)> {
+        let mut speeds = self
+            .characters
+            .iter()
+            .filter(|(_, character)| character.is_alive() && character.has_turns())
+            .map(|(id, character)| {
+                let class = g.classes.get(character.class).expect("Invalid class id");
+                (*id, i64::from(character.effective_swiftness(class)).max(1))
+            })
+            .collect::<Vec<_>>();
+        speeds.sort_by_key(|(id, _)| id.raw());
+        speeds
+    }
 
-        let mut turn_order = self.characters.iter().collect::<Vec<_>>();
-        turn_order.sort_by_key(|(_, character)| {
-            let class = g.classes.get(character.class).expect("Invalid class id");
-            character.effective_swiftness(class)
-        });
-        self.turn_order = turn_order.iter().map(|(id, _)| **id).collect::<Vec<_>>();
+    /// Advances the timeline up to the next character reaching the initiative threshold, and
+    /// consumes their turn.
+    /// Ties are broken by the initiative overflow, then the speed, then the lowest id.
+    fn next_actor(
+        initiative: &mut HashMap<Id<Character>, i64>,
+        speeds: &[(Id<Character>, i64)],
+    ) -> Id<Character> {
+        let ticks_needed = |id: &Id<Character>, speed: i64, initiative: &HashMap<_, i64>| {
+            let missing = INITIATIVE_THRESHOLD - initiative.get(id).cloned().unwrap_or(0);
+            if missing <= 0 {
+                0
+            } else {
+                (missing + speed - 1) / speed
+            }
+        };
+
+        let ticks = speeds
+            .iter()
+            .map(|(id, speed)| ticks_needed(id, *speed, initiative))
+            .min()
+            .expect("No character alive to play");
+        for (id, speed) in speeds {
+            *initiative.entry(*id).or_insert(0) += ticks * speed;
+        }
+
+        let (actor, _) = speeds
+            .iter()
+            .map(|(id, speed)| (*id, (initiative[id], *speed, std::cmp::Reverse(id.raw()))))
+            .filter(|(_, (init, _, _))| *init >= INITIATIVE_THRESHOLD)
+            .max_by_key(|(_, key)| *key)
+            .expect("At least one character reached the threshold");
+        *initiative.get_mut(&actor).unwrap() -= INITIATIVE_THRESHOLD;
+        actor
+    }
+
+    /// Characters who will play the next `n` turns, starting with the one currently playing.
+    /// Assumes nobody dies and no swiftness changes in the meantime.
+    pub fn upcoming_turns(&self, g: &GameDefinition, n: usize) -> Vec<Id<Character>> {
+        let speeds = self.speeds(g);
+        if speeds.is_empty() {
+            return Vec::new();
+        }
+
+        let mut initiative = self.initiative.clone();
+        let mut turns = self.current.into_iter().collect::<Vec<_>>();
+        while turns.len() < n {
+            turns.push(GameState::next_actor(&mut initiative, &speeds));
+        }
+        turns.truncate(n);
+        turns
     }
 
     pub fn player_to_play(&self) -> Id<Character> {
-        self.turn_order
-            .last()
-            .cloned()
-            .expect("Turn is finished and should be reset")
+        self.current.expect("The game has not started yet")
+    }
+
+    /// The game is over once a team completed the objectives of the game mode, or at most one
+    /// team has characters left alive (summons do not count)
+    pub fn outcome(&self) -> Outcome {
+        let mut alive_teams = self
+            .characters
+            .iter()
+            .filter(|(_, character)| character.is_alive() && character.summoned.is_none())
+            .map(|(_, character)| character.team)
+            .collect::<Vec<_>>();
+        alive_teams.sort_by_key(|team| team.raw());
+        alive_teams.dedup();
+
+        match self.mode {
+            GameMode::Deathmatch => {}
+            GameMode::CapturePoints { score, .. } | GameMode::KingOfTheHill { rounds: score } => {
+                let mut winners = self
+                    .scores
+                    .iter()
+                    .filter(|(_, points)| **points >= score)
+                    .map(|(team, _)| *team)
+                    .collect::<Vec<_>>();
+                winners.sort_by_key(|team| team.raw());
+                match winners.as_slice() {
+                    [] => {}
+                    [team] => return Outcome::Victory(*team),
+                    _ => return Outcome::Draw,
+                }
+            }
+            GameMode::Escort { team } => {
+                if self.scores.contains_key(&team) {
+                    return Outcome::Victory(team);
+                }
+                let escort_dead = self
+                    .escort(team)
+                    .and_then(|id| self.characters.get(id))
+                    .is_some_and(|escort| !escort.is_alive());
+                if escort_dead {
+                    alive_teams.retain(|alive_team| *alive_team != team);
+                }
+            }
+        }
+
+        match alive_teams.as_slice() {
+            [] => Outcome::Draw,
+            [team] => Outcome::Victory(*team),
+            _ => Outcome::Ongoing,
+        }
     }
 
-    pub fn next_action(&mut self, g: &GameDefinition, ga: Action) -> Result<bool, Error> {
+    /// Returns the events resulting from the action
+    pub fn next_action(&mut self, g: &GameDefinition, ga: Action) -> Result<Vec<GameEvent>, Error> {
+        if self.outcome() != Outcome::Ongoing {
+            return Err(Error::GameOver);
+        }
+        let first_event = self.log.len();
+
+        let curr_id = self.player_to_play();
         let curr_char = self
             .characters
-            .get(
-                *self
-                    .turn_order
-                    .last()
-                    .expect("Turn is finished and should be reset"),
-            )
+            .get(curr_id)
             .expect("Invalid character id")
             .clone();
 
+        let class = g.classes.get(curr_char.class).expect("Invalid class id");
+        let swiftness = curr_char.effective_swiftness(class);
         match (ga, self.turn_state) {
-            (Action::Pass, _) => {
-                self.turn_order.pop();
+            (Action::Pass, _) | (Action::EndTurn, _) => {}
+            (
+                Action::Skill(skill_id, cell_id),
+                TurnState::Points {
+                    action_points,
+                    movement_points,
+                },
+            ) => {
+                let skill = g.skills.get(skill_id).ok_or(Error::InvalidSkill)?;
+                if skill.action_points > action_points {
+                    return Err(Error::NotEnoughActionPoints);
+                }
+                self.execute_skill(curr_id, curr_char, g, skill_id, cell_id)?;
+                self.turn_state = TurnState::Points {
+                    action_points: action_points - skill.action_points,
+                    movement_points,
+                };
+                // the turn goes on until explicitly ended, unless the caster died in the process
+                // or the skill ended the game
+                let character = self.characters.get(curr_id).expect("Invalid character id");
+                if character.is_alive() && self.outcome() == Outcome::Ongoing {
+                    return Ok(self.log[first_event..].to_vec());
+                }
             }
             (Action::Skill(skill_id, cell_id), _) => {
-                self.execute_skill(curr_char, g, skill_id, cell_id)?;
-                self.turn_order.pop();
+                self.execute_skill(curr_id, curr_char, g, skill_id, cell_id)?;
+            }
+            (
+                Action::Movement(cell_id),
+                TurnState::Points {
+                    action_points,
+                    movement_points,
+                },
+            ) => {
+                let cost =
+                    self.execute_move(curr_id, curr_char, g, cell_id, movement_points as i32)?;
+                self.turn_state = TurnState::Points {
+                    action_points,
+                    movement_points: movement_points - cost,
+                };
+                let character = self.characters.get(curr_id).expect("Invalid character id");
+                if character.is_alive() && self.outcome() == Outcome::Ongoing {
+                    return Ok(self.log[first_event..].to_vec());
+                }
             }
             (Action::Movement(cell_id), TurnState::MoveOrAction) => {
-                self.execute_move(curr_char, g, cell_id)?;
+                self.execute_move(curr_id, curr_char, g, cell_id, swiftness)?;
+                self.turn_state = TurnState::ActionOnly;
+                // the turn goes on, unless the character died on the way (e.g. on a trap) or
+                // completed an objective
+                let character = self.characters.get(curr_id).expect("Invalid character id");
+                if character.is_alive() && self.outcome() == Outcome::Ongoing {
+                    return Ok(self.log[first_event..].to_vec());
+                }
             }
             (Action::Movement(_), TurnState::ActionOnly) => return Err(Error::AlreadyMoved),
         }
 
-        Ok(if self.turn_order.is_empty() {
-            self.new_turn(g);
-            true
-        } else {
-            false
-        })
+        self.end_turn();
+        self.next_turn(g);
+        Ok(self.log[first_event..].to_vec())
     }
 
-    fn player_at(&self, cell_id: Id<Cell>) -> Option<(&Id<Character>, &Character)> {
-        self.characters
+    /// Validates the action (by running it on a copy of the game state) and predicts its
+    /// outcome, without altering the game state
+    pub fn preview_action(&self, g: &GameDefinition, ga: Action) -> Result<ActionPreview, Error> {
+        self.clone().next_action(g, ga.clone())?;
+
+        let curr_id = self.player_to_play();
+        let curr_char = self.characters.get(curr_id).expect("Invalid character id");
+        let mut preview = ActionPreview {
+            mana_after: curr_char.current_mana,
+            cells: Vec::new(),
+            targets: Vec::new(),
+        };
+
+        match ga {
+            Action::Pass | Action::EndTurn => {}
+            Action::Movement(cell_id) => {
+                let class = g.classes.get(curr_char.class).expect("Invalid class id");
+                preview.cells = g
+                    .maps
+                    .get(self.map)
+                    .expect("Invalid game map id")
+                    .find_path(
+                        curr_char.position,
+                        cell_id,
+                        std::cmp::max(0, curr_char.effective_swiftness(class)) as u32,
+                        &self.occupied_cells(curr_id),
+                    )
+                    .expect("Movement was validated");
+            }
+            Action::Skill(skill_id, cell_id) => {
+                let skill = g.skills.get(skill_id).expect("Skill was validated");
+                let class = g.classes.get(curr_char.class).expect("Invalid class id");
+                let caster_stats = curr_char.effective_stats(class);
+                preview.mana_after -= skill.cost;
+
+                let mut effects = skill.effects.iter().collect::<Vec<_>>();
+                effects.sort_by_key(|id| id.raw());
+                for effect_id in effects {
+                    let effect = g.effects.get(*effect_id).expect("Invalid effect id");
+                    let area = match &effect.kind {
+                        EffectKind::Buff(buff) => &buff.range,
+                        EffectKind::DirectDamage(direct_damage) => &direct_damage.range,
+                        EffectKind::Heal(direct_heal) => &direct_heal.range,
+                        EffectKind::Displacement(displacement) => &displacement.range,
+                        EffectKind::Summon(_) => &None,
+                        EffectKind::CellEffect(cell_effect) => &cell_effect.range,
+                    };
+
+                    for cell in self.area_cells(g, curr_char.position, cell_id, area) {
+                        if !preview.cells.contains(&cell) {
+                            preview.cells.push(cell);
+                        }
+                    }
+                    // nobody is affected until the cell effect is triggered
+                    if let EffectKind::CellEffect(_) = effect.kind {
+                        continue;
+                    }
+
+                    for (id, target) in
+                        self.affected_characters(g, curr_char, cell_id, effect, area)
+                    {
+                        let hit_chance =
+                            GameState::hit_chance(g, skill, curr_char, &caster_stats, target);
+                        let index = match preview.targets.iter().position(|t| t.character == id) {
+                            Some(index) => index,
+                            None => {
+                                preview.targets.push(TargetPreview {
+                                    character: id,
+                                    hit_chance,
+                                    min_damage: 0,
+                                    max_damage: 0,
+                                    damage: Default::default(),
+                                    heal: 0,
+                                    buffs: Vec::new(),
+                                });
+                                preview.targets.len() - 1
+                            }
+                        };
+                        let target_preview = &mut preview.targets[index];
+
+                        match &effect.kind {
+                            EffectKind::Buff(buff) => {
+                                let chance = hit_chance * buff.success_rate.unwrap_or(1.0);
+                                target_preview.buffs.push((*effect_id, chance));
+                            }
+                            EffectKind::DirectDamage(direct_damage) => {
+                                let target_class =
+                                    g.classes.get(target.class).expect("Invalid class id");
+                                let damage = direct_damage.damage.compute_damage(
+                                    &caster_stats,
+                                    &target.effective_stats(target_class),
+                                );
+                                target_preview.max_damage += damage.total();
+                                target_preview.damage += damage;
+                                if hit_chance >= 1.0 {
+                                    target_preview.min_damage += damage.total();
+                                }
+                            }
+                            EffectKind::Heal(direct_heal) => {
+                                let target_class =
+                                    g.classes.get(target.class).expect("Invalid class id");
+                                target_preview.heal += direct_heal.heal.compute_heal(
+                                    &caster_stats,
+                                    &target.effective_stats(target_class),
+                                );
+                            }
+                            EffectKind::Displacement(_)
+                            | EffectKind::Summon(_)
+                            | EffectKind::CellEffect(_) => {}
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(preview)
+    }
+
+    /// Starts the turn of the next character alive, starting a new round if needed. Characters
+    /// killed at the start of their turn (e.g. by a DoT) are skipped, and summons play on their
+    /// own until a player's turn starts.
+    fn next_turn(&mut self, g: &GameDefinition) {
+        loop {
+            let outcome = self.outcome();
+            if outcome != Outcome::Ongoing {
+                self.log.push(GameEvent::GameOver(outcome));
+                return;
+            }
+
+            let speeds = self.speeds(g);
+            let actor = GameState::next_actor(&mut self.initiative, &speeds);
+
+            // A round is over once every character alive has played at least once
+            let round_over = speeds
+                .iter()
+                .all(|(id, _)| self.played_this_round.contains(id));
+            if self.turn > 0 && round_over {
+                self.score_objectives(g);
+                let outcome = self.outcome();
+                if outcome != Outcome::Ongoing {
+                    self.log.push(GameEvent::GameOver(outcome));
+                    return;
+                }
+            }
+            if self.turn == 0 || round_over {
+                self.turn += 1;
+                self.played_this_round.clear();
+                self.log.push(GameEvent::RoundStarted(self.turn));
+            }
+
+            self.current = Some(actor);
+            self.played_this_round.insert(actor);
+            if self.start_turn(g) {
+                match self.characters.get(actor).map(|c| &c.summoned) {
+                    Some(Some(summoned)) if summoned.behaviour == Behaviour::Attack => {
+                        self.play_ai(g)
+                    }
+                    _ => return,
+                }
+            }
+            self.end_turn();
+        }
+    }
+
+    /// Resolves the cell effects, the DoTs then the HoTs affecting the character whose turn is
+    /// starting, then ticks down the duration of their buffs. Returns false if the character
+    /// cannot play this turn i.e. they died in the process or are stunned.
+    fn start_turn(&mut self, g: &GameDefinition) -> bool {
+        let curr_id = self.player_to_play();
+        self.log.push(GameEvent::TurnStarted(curr_id));
+        self.turn_state = match self.rules.turns {
+            TurnRules::MoveThenAct => TurnState::MoveOrAction,
+            TurnRules::ActionPoints { action_points } => {
+                let character = self.characters.get(curr_id).expect("Invalid character id");
+                let class = g.classes.get(character.class).expect("Invalid class id");
+                TurnState::Points {
+                    action_points,
+                    movement_points: std::cmp::max(0, character.effective_swiftness(class)) as u32,
+                }
+            }
+        };
+        self.tick_summons(curr_id);
+        self.tick_cell_effects(curr_id);
+
+        let position = self
+            .characters
+            .get(curr_id)
+            .expect("Invalid character id")
+            .position;
+        self.trigger_cell_effects(g, curr_id, position, Trigger::TurnStart);
+        if !self
+            .characters
+            .get(curr_id)
+            .expect("Invalid character id")
+            .is_alive()
+        {
+            return false;
+        }
+
+        let character = self.characters.get(curr_id).expect("Invalid character id");
+        let class = g.classes.get(character.class).expect("Invalid class id");
+        let stats = character.effective_stats(class);
+
+        let dots = character
+            .buffs
+            .iter()
+            .filter_map(|instance| match &instance.buff.kind {
+                BuffKind::DoT(damage) => Some((
+                    instance.source,
+                    damage.compute_damage(&self.caster_stats(g, instance), &stats),
+                )),
+                BuffKind::HoT(_)
+                | BuffKind::Attribute(_, _)
+                | BuffKind::Status(_)
+                | BuffKind::Shield(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let hots = character
+            .buffs
             .iter()
-            .find(|(_, character)| character.position == cell_id)
+            .filter_map(|instance| match &instance.buff.kind {
+                BuffKind::HoT(heal) => Some((
+                    instance.source,
+                    heal.compute_heal(&self.caster_stats(g, instance), &stats),
+                    heal.overheal,
+                )),
+                BuffKind::DoT(_)
+                | BuffKind::Attribute(_, _)
+                | BuffKind::Status(_)
+                | BuffKind::Shield(_) => None,
+            })
+            .collect::<Vec<_>>();
+        for (source, damage) in dots {
+            self.apply_damage(source, curr_id, damage);
+        }
+        for (source, amount, overheal) in hots {
+            self.apply_heal(g, source, curr_id, amount, overheal);
+        }
+
+        let character = self
+            .characters
+            .get_mut(curr_id)
+            .expect("Invalid character id");
+        if !character.is_alive() {
+            return false;
+        }
+        character.regenerate_mana(class);
+        let expired_buffs = character.tick_buffs();
+        character.clamp_health(class);
+        for expired in expired_buffs {
+            self.log.push(GameEvent::BuffExpired {
+                target: curr_id,
+                effect: expired.effect,
+            });
+        }
+
+        if let Some(cooldowns) = self.cooldowns.get_mut(&curr_id) {
+            for (skill_id, cooldown) in cooldowns.iter_mut() {
+                let skill = g.skills.get(*skill_id).expect("Invalid skill id");
+                skill.tick_cooldown(cooldown);
+            }
+        }
+
+        let character = self.characters.get(curr_id).expect("Invalid character id");
+        if character.has_status(Status::Stun) {
+            self.log.push(GameEvent::TurnSkipped(curr_id));
+            return false;
+        }
+        true
     }
 
-    fn execute_skill(
+    fn apply_damage(
         &mut self,
-        curr_char: Character,
-        g: &GameDefinition,
-        skill_id: Id<Skill>,
-        cell_id: Id<Cell>,
-    ) -> Result<(), Error> {
-        let skill = g.skills.get(skill_id).ok_or(Error::InvalidSkill)?;
-        let target = self.player_at(cell_id);
+        source: Id<Character>,
+        target: Id<Character>,
+        breakdown: DamageBreakdown,
+    ) {
+        let character = self
+            .characters
+            .get_mut(target)
+            .expect("Invalid character id");
+        let (amount, absorptions) = character.absorb_damage(&breakdown);
+        let absorbed = breakdown.total() - amount;
 
-        if !GameState::check_target(&curr_char, &target, skill.range.target) {
-            return Err(Error::InvalidTarget);
+        let was_alive = character.is_alive();
+        character.current_health -= amount;
+        let died = was_alive && !character.is_alive();
+
+        self.log.push(GameEvent::Damaged {
+            source: Some(source),
+            target,
+            amount,
+            absorbed,
+            breakdown,
+        });
+        for absorption in absorptions {
+            self.log.push(GameEvent::Absorbed {
+                source: Some(absorption.source),
+                target,
+                effect: absorption.effect,
+                amount: absorption.amount,
+            });
+            if absorption.depleted {
+                self.log.push(GameEvent::BuffExpired {
+                    target,
+                    effect: absorption.effect,
+                });
+            }
+        }
+        if died {
+            self.log.push(GameEvent::Died(target));
         }
+    }
 
-        if !self.check_range(g, curr_char.position, cell_id, skill.range) {
-            return Err(Error::InvalidRange);
+    /// Dead characters cannot be healed back to life
+    fn apply_heal(
+        &mut self,
+        g: &GameDefinition,
+        source: Id<Character>,
+        target: Id<Character>,
+        amount: i32,
+        overheal: Overheal,
+    ) {
+        let character = self
+            .characters
+            .get_mut(target)
+            .expect("Invalid character id");
+        if !character.is_alive() {
+            return;
         }
+        let class = g.classes.get(character.class).expect("Invalid class id");
+        let (amount, shield) = character.heal(class, amount, overheal);
+        self.log.push(GameEvent::Healed {
+            source: Some(source),
+            target,
+            amount,
+            shield,
+        });
+    }
 
-        // TODO check LOS
+    /// Spawns the unit on the cell, unless something moved there in the meantime (e.g. a
+    /// displaced character)
+    fn summon(
+        &mut self,
+        g: &GameDefinition,
+        owner: Id<Character>,
+        cell: Id<Cell>,
+        summon: &Summon,
+    ) {
+        if self.player_at(cell).is_some() {
+            return;
+        }
+        let class = g.classes.get(summon.class).expect("Invalid class id");
+        let team = self
+            .characters
+            .get(owner)
+            .expect("Invalid character id")
+            .team;
+        let mut character = Character::new(summon.class, cell, class, class.name.clone(), team);
+        character.summoned = Some(Summoned {
+            owner,
+            behaviour: summon.behaviour,
+            remaining_turns: summon.lifetime,
+        });
+        let id = self.next_summon;
+        self.next_summon = Id::new(id.raw() + 1);
+        self.characters.insert(id, character);
+        self.log.push(GameEvent::Summoned {
+            owner,
+            summon: id,
+            cell,
+        });
+    }
 
-        // FIXME: this is a borrow-checker workaround... but probably the only actual one..?
-        let mut game_state_updates = HashSet::<(Id<Character>, i32)>::new();
-        // TODO: compute if hit?
-        if true {
-            if let Some((id, target)) = target {
-                for effect in &skill.effects {
-                    let effect = g.effects.get(*effect).expect("Invalid effect id");
-                    match &effect.kind {
-                        // TODO
-                        EffectKind::Buff(_) => unimplemented!(),
-
-                        // TODO: add somewhere if skills can attack other cells than just the
-                        // target
-                        EffectKind::DirectDamage(direct_damage) => {
-                            let damage = direct_damage
-                                .damage
-                                .compute_damage(&g.classes, &curr_char, target);
-                            game_state_updates.insert((*id, damage));
-                        }
+    /// Decrements the lifetime of the summons of the character whose turn is starting, and
+    /// removes the expired ones from the game, as well as the summons of the dead characters
+    fn tick_summons(&mut self, owner: Id<Character>) {
+        let dead = self
+            .characters
+            .iter()
+            .filter(|(_, character)| !character.is_alive())
+            .map(|(id, _)| *id)
+            .collect::<HashSet<_>>();
+        let mut expired = Vec::new();
+        for (id, character) in self.characters.iter_mut() {
+            match &mut character.summoned {
+                // the owner will never play again to tick the lifetime down
+                Some(Summoned {
+                    owner: summon_owner,
+                    ..
+                }) if dead.contains(summon_owner) && character.current_health > 0 => {
+                    expired.push((*id, *summon_owner, character.position));
+                }
+                Some(Summoned {
+                    owner: summon_owner,
+                    remaining_turns: Some(remaining_turns),
+                    ..
+                }) if *summon_owner == owner && character.current_health > 0 => {
+                    *remaining_turns = remaining_turns.saturating_sub(1);
+                    if *remaining_turns == 0 {
+                        expired.push((*id, *summon_owner, character.position));
                     }
                 }
+                _ => {}
             }
         }
 
-        // TODO stuff if dead
-        for (id, damage) in game_state_updates {
-            let character = self.characters.get_mut(id).expect("Invalid character id");
-            character.current_health -= damage;
+        expired.sort_by_key(|(id, _, _)| id.raw());
+        let ids = expired.iter().map(|(id, _, _)| *id).collect::<HashSet<_>>();
+        self.characters.retain(|id, _| !ids.contains(id));
+        self.initiative.retain(|id, _| !ids.contains(id));
+        self.played_this_round.retain(|id| !ids.contains(id));
+        self.cooldowns.retain(|id, _| !ids.contains(id));
+        for (summon, owner, cell) in expired {
+            self.log.push(GameEvent::SummonExpired {
+                summon,
+                owner,
+                cell,
+            });
         }
-
-        Ok(())
     }
 
-    fn check_target(
-        attacker: &Character,
-        target_opt: &Option<(&Id<Character>, &Character)>,
-        target_kind: Target,
-    ) -> bool {
-        if let Some((_, target)) = target_opt {
-            match (target_kind, target.team == attacker.team) {
-                (Target::Anything, _) | (Target::Anyone, _) => true,
-                (Target::Enemy, is_same_team) => !is_same_team,
-                (Target::Ally, is_same_team) => is_same_team,
+    /// Turn of a summon with the `Attack` behaviour: it does not move, and casts the first of its
+    /// skills which can reach an enemy, trying the closest ones first
+    fn play_ai(&mut self, g: &GameDefinition) {
+        let curr_id = self.player_to_play();
+        let curr_char = self
+            .characters
+            .get(curr_id)
+            .expect("Invalid character id")
+            .clone();
+        let map = g.maps.get(self.map).expect("Invalid map id");
+
+        let mut enemies = self
+            .characters
+            .iter()
+            .filter(|(_, character)| character.is_alive() && character.team != curr_char.team)
+            .map(|(id, character)| {
+                (
+                    map.distance(curr_char.position, character.position),
+                    id.raw(),
+                    character.position,
+                )
+            })
+            .collect::<Vec<_>>();
+        enemies.sort_by_key(|(distance, id, _)| (*distance, *id));
+
+        let mut skills = g
+            .class_to_skills
+            .get(&curr_char.class)
+            .cloned()
+            .unwrap_or_default();
+        skills.sort_by_key(|id| id.raw());
+        for skill_id in skills {
+            for (_, _, cell) in &enemies {
+                if self
+                    .execute_skill(curr_id, curr_char.clone(), g, skill_id, *cell)
+                    .is_ok()
+                {
+                    return;
+                }
             }
-        } else {
-            Target::Anything == target_kind
         }
     }
 
-    fn check_range(
-        &self,
+    fn kill(&mut self, target: Id<Character>) {
+        let character = self
+            .characters
+            .get_mut(target)
+            .expect("Invalid character id");
+        if character.is_alive() {
+            character.current_health = 0;
+            self.log.push(GameEvent::Died(target));
+        }
+    }
+
+    fn displace(&mut self, g: &GameDefinition, target: Id<Character>, to: Id<Cell>) {
+        let character = self
+            .characters
+            .get_mut(target)
+            .expect("Invalid character id");
+        let from = character.position;
+        character.position = to;
+        self.log.push(GameEvent::Displaced {
+            character: target,
+            from,
+            to,
+        });
+        self.trigger_cell_effects(g, target, from, Trigger::Leave);
+        self.trigger_cell_effects(g, target, to, Trigger::Enter);
+        self.check_exit(g, target);
+    }
+
+    /// Moves the character onto a neighbouring cell, walking or displaced, triggering the cell
+    /// effects of the cell left then of the one entered. Returns false if the character cannot
+    /// go any further i.e. a cell effect killed or rooted them on the way.
+    fn step(
+        &mut self,
         g: &GameDefinition,
-        start: Id<Cell>,
-        end: Id<Cell>,
-        range: Range,
+        character_id: Id<Character>,
+        to: Id<Cell>,
+        displaced: bool,
     ) -> bool {
-        let map = g.maps.get(self.map).expect("Invalid game map id");
+        let can_go_on = |gs: &GameState| {
+            let character = gs
+                .characters
+                .get(character_id)
+                .expect("Invalid character id");
+            character.is_alive() && !character.has_status(Status::Root)
+        };
 
-        match range.kind {
-            RangeKind::Star => {
-                let distance = map.distance(start, end);
-                distance <= range.max && distance >= range.min
+        let from = self
+            .characters
+            .get(character_id)
+            .expect("Invalid character id")
+            .position;
+        self.trigger_cell_effects(g, character_id, from, Trigger::Leave);
+        if !can_go_on(self) {
+            return false;
+        }
+
+        self.characters
+            .get_mut(character_id)
+            .expect("Invalid character id")
+            .position = to;
+        self.log.push(if displaced {
+            GameEvent::Displaced {
+                character: character_id,
+                from,
+                to,
+            }
+        } else {
+            GameEvent::Moved {
+                character: character_id,
+                from,
+                to,
             }
-            RangeKind::Cross => {
-                let (sx, sy) = map.id_to_xy_i32(start);
-                let (ex, ey) = map.id_to_xy_i32(end);
+        });
+        self.trigger_cell_effects(g, character_id, to, Trigger::Enter);
+        can_go_on(self)
+    }
+
+    /// Character escorted by the team with the `Escort` mode
+    fn escort(&self, team: Id<Team>) -> Option<Id<Character>> {
+        self.characters
+            .iter()
+            .find(|(_, character)| character.team == team && character.escort)
+            .map(|(id, _)| *id)
+    }
+
+    /// Completes the objective of the `Escort` mode if the character is the escort, standing
+    /// alive on an exit
+    fn check_exit(&mut self, g: &GameDefinition, character_id: Id<Character>) {
+        let team = match self.mode {
+            GameMode::Escort { team } => team,
+            _ => return,
+        };
+        let character = self
+            .characters
+            .get(character_id)
+            .expect("Invalid character id");
+        let map = g.maps.get(self.map).expect("Invalid game map id");
+        if self.escort(team) == Some(character_id)
+            && character.is_alive()
+            && map.objectives.exits.contains(&character.position)
+        {
+            self.scores.insert(team, 1);
+            self.log.push(GameEvent::ExitReached(character_id));
+        }
+    }
 
-                let dx = sx - ex;
-                let dy = sy - ey;
+    /// Team standing alone on the given cells, if any (summons do not count)
+    fn holder(&self, cells: &[Id<Cell>]) -> Option<Id<Team>> {
+        let mut teams = self
+            .characters
+            .iter()
+            .filter(|(_, character)| {
+                character.is_alive()
+                    && character.summoned.is_none()
+                    && cells.contains(&character.position)
+            })
+            .map(|(_, character)| character.team)
+            .collect::<Vec<_>>();
+        teams.sort_by_key(|team| team.raw());
+        teams.dedup();
+        match teams.as_slice() {
+            [team] => Some(*team),
+            _ => None,
+        }
+    }
 
-                let max = range.max as i32;
-                let min = range.min as i32;
+    /// Updates the capture zones and the scores at the end of a round, depending on the game mode
+    fn score_objectives(&mut self, g: &GameDefinition) {
+        let objectives = &g
+            .maps
+            .get(self.map)
+            .expect("Invalid game map id")
+            .objectives;
 
-                dx == 0 && dy.abs() <= max && dy.abs() >= min
-                    || dy == 0 && dx.abs() <= max && dx.abs() >= min
+        let mut points = HashMap::new();
+        match self.mode {
+            GameMode::Deathmatch | GameMode::Escort { .. } => {}
+            GameMode::CapturePoints { capture_rounds, .. } => {
+                for (zone, cells) in objectives.capture_zones.iter().enumerate() {
+                    let holder = self.holder(cells);
+                    if self.captures[zone].update(holder, capture_rounds) {
+                        self.log.push(GameEvent::ZoneCaptured {
+                            zone,
+                            team: holder.expect("Zones are captured by a team"),
+                        });
+                    }
+                }
+                for owner in self.captures.iter().filter_map(|capture| capture.owner) {
+                    *points.entry(owner).or_insert(0) += 1;
+                }
             }
-            RangeKind::Square => {
-                let (sx, sy) = map.id_to_xy_i32(start);
-                let (ex, ey) = map.id_to_xy_i32(end);
-
-                let dx = sx - ex;
-                let dy = sy - ey;
+            GameMode::KingOfTheHill { .. } => {
+                let holder = self.holder(&objectives.hill);
+                // the hill must be held for consecutive rounds
+                let mut lost = self
+                    .scores
+                    .iter()
+                    .filter(|(team, score)| Some(**team) != holder && **score > 0)
+                    .map(|(team, _)| *team)
+                    .collect::<Vec<_>>();
+                lost.sort_by_key(|team| team.raw());
+                for team in lost {
+                    self.scores.insert(team, 0);
+                    self.log.push(GameEvent::Scored { team, score: 0 });
+                }
+                if let Some(team) = holder {
+                    points.insert(team, 1);
+                }
+            }
+        }
 
-                let max = range.max as i32;
-                let min = range.min as i32;
+        let mut points = points.into_iter().collect::<Vec<_>>();
+        points.sort_by_key(|(team, _)| team.raw());
+        for (team, points) in points {
+            let score = self.scores.entry(team).or_insert(0);
+            *score += points;
+            self.log.push(GameEvent::Scored {
+                team,
+                score: *score,
+            });
+        }
+    }
 
-                dx.abs() <= max && dx.abs() >= min && dy >= min && dy <= max
+    /// Decrements the duration of the cell effects created by the character whose turn is
+    /// starting, and removes the expired ones as well as the ones of the characters who died or
+    /// left the game
+    fn tick_cell_effects(&mut self, source: Id<Character>) {
+        let alive = self
+            .characters
+            .iter()
+            .filter(|(_, character)| character.is_alive())
+            .map(|(id, _)| *id)
+            .collect::<HashSet<_>>();
+        let mut cells = self.cell_effects.keys().cloned().collect::<Vec<_>>();
+        cells.sort_by_key(|cell| cell.raw());
+        for cell in cells {
+            let instances = self.cell_effects.get_mut(&cell).expect("Invalid cell id");
+            for instance in instances.iter_mut() {
+                if !alive.contains(&instance.source) {
+                    // the source (dead, or an expired summon) will never play again to tick it
+                    // down
+                    instance.remaining_duration = 0;
+                } else if instance.source == source {
+                    instance.remaining_duration = instance.remaining_duration.saturating_sub(1);
+                }
+            }
+            let (expired, active): (Vec<_>, Vec<_>) = instances
+                .drain(..)
+                .partition(|instance| instance.remaining_duration == 0);
+            *instances = active;
+            if instances.is_empty() {
+                self.cell_effects.remove(&cell);
+            }
+            for instance in expired {
+                self.log.push(GameEvent::CellEffectExpired {
+                    cell,
+                    effect: instance.effect,
+                });
             }
         }
     }
 
-    fn execute_move(
+    /// Applies the cell effects of the cell to the character, if they react to the trigger
+    fn trigger_cell_effects(
         &mut self,
-        curr_char: Character,
         g: &GameDefinition,
-        cell_id: Id<Cell>,
-    ) -> Result<(), Error> {
-        assert!(!self.turn_order.is_empty());
+        character_id: Id<Character>,
+        cell: Id<Cell>,
+        trigger: Trigger,
+    ) {
+        let character = self
+            .characters
+            .get(character_id)
+            .expect("Invalid character id");
+        let team = character.team;
+        let instances = match self.cell_effects.get_mut(&cell) {
+            Some(instances) if character.is_alive() => instances,
+            _ => return,
+        };
 
-        let class = g.classes.get(curr_char.class).expect("Invalid class id");
-        if g.maps
-            .get(self.map)
-            .expect("Invalid game map id")
-            .can_move_to(
-                curr_char.position,
-                cell_id,
-                curr_char.effective_swiftness(class),
-            )
-        {
-            self.turn_state = TurnState::ActionOnly;
-            Ok(())
-        } else {
-            Err(Error::MoveCellTooFar)
+        let is_triggered = |instance: &CellEffectInstance| {
+            instance.cell_effect.triggers.contains(&trigger)
+                && match instance.cell_effect.target {
+                    Target::Anything | Target::Anyone => true,
+                    Target::Enemy => instance.team != team,
+                    Target::Ally => instance.team == team,
+                }
+        };
+        let triggered = instances
+            .iter()
+            .filter(|instance| is_triggered(instance))
+            .cloned()
+            .collect::<Vec<_>>();
+        // single use effects disappear from every cell they cover
+        let used = triggered
+            .iter()
+            .filter(|instance| instance.cell_effect.single_use)
+            .map(|instance| (instance.effect, instance.source))
+            .collect::<Vec<_>>();
+        let mut cells = self.cell_effects.keys().cloned().collect::<Vec<_>>();
+        cells.sort_by_key(|cell| cell.raw());
+        for other_cell in cells {
+            let instances = self
+                .cell_effects
+                .get_mut(&other_cell)
+                .expect("Invalid cell id");
+            let (removed, kept): (Vec<_>, Vec<_>) = instances
+                .drain(..)
+                .partition(|instance| used.contains(&(instance.effect, instance.source)));
+            *instances = kept;
+            if instances.is_empty() {
+                self.cell_effects.remove(&other_cell);
+            }
+            if other_cell != cell {
+                for instance in removed {
+                    self.log.push(GameEvent::CellEffectExpired {
+                        cell: other_cell,
+                        effect: instance.effect,
+                    });
+                }
+            }
         }
+
+        for instance in triggered {
+            self.log.push(GameEvent::CellEffectTriggered {
+                cell,
+                effect: instance.effect,
+                character: character_id,
+            });
+            let mut effects = instance.cell_effect.effects.clone();
+            effects.sort_by_key(|id| id.raw());
+            for effect in effects {
+                self.apply_effect(
+                    g,
+                    instance.source,
+                    &instance.caster_stats,
+                    character_id,
+                    effect,
+                );
+            }
+        }
+    }
+
+    /// Applies a damage, heal or buff effect to a single character, without any hit roll e.g. for
+    /// cell effects
+    fn apply_effect(
+        &mut self,
+        g: &GameDefinition,
+        source: Id<Character>,
+        caster_stats: &Stats,
+        target: Id<Character>,
+        effect_id: Id<Effect>,
+    ) {
+        let character = self.characters.get(target).expect("Invalid character id");
+        if !character.is_alive() {
+            return;
+        }
+        let class = g.classes.get(character.class).expect("Invalid class id");
+        let stats = character.effective_stats(class);
+
+        let effect = g.effects.get(effect_id).expect("Invalid effect id");
+        match &effect.kind {
+            EffectKind::DirectDamage(direct_damage) => {
+                let damage = direct_damage.damage.compute_damage(caster_stats, &stats);
+                self.apply_damage(source, target, damage);
+            }
+            EffectKind::Heal(direct_heal) => {
+                let amount = direct_heal.heal.compute_heal(caster_stats, &stats);
+                self.apply_heal(g, source, target, amount, direct_heal.heal.overheal);
+            }
+            EffectKind::Buff(buff) => {
+                let instance = BuffInstance::new(effect_id, buff, source, *caster_stats, self.turn);
+                let character = self
+                    .characters
+                    .get_mut(target)
+                    .expect("Invalid character id");
+                if character.add_buff(instance) {
+                    self.log.push(GameEvent::BuffApplied {
+                        source: Some(source),
+                        target,
+                        effect: effect_id,
+                    });
+                }
+            }
+            EffectKind::Displacement(_) | EffectKind::Summon(_) | EffectKind::CellEffect(_) => {}
+        }
+    }
+
+    /// Moves the target (or the caster when teleporting) according to the displacement. Pushed
+    /// and pulled characters move one cell at a time and stop at the first obstacle, taking
+    /// collision damage (as does the character they bumped into). Falling into a hole is lethal.
+    fn apply_displacement(
+        &mut self,
+        g: &GameDefinition,
+        caster_id: Id<Character>,
+        caster_stats: &Stats,
+        target_id: Id<Character>,
+        cell_id: Id<Cell>,
+        displacement: &Displacement,
+    ) {
+        let map = g.maps.get(self.map).expect("Invalid map id");
+        let caster = self
+            .characters
+            .get(caster_id)
+            .expect("Invalid character id");
+        let target = self
+            .characters
+            .get(target_id)
+            .expect("Invalid character id");
+        if !caster.is_alive() || !target.is_alive() {
+            return;
+        }
+        let (caster_position, from) = (caster.position, target.position);
+
+        let (direction, steps) = match displacement.kind {
+            DisplacementKind::Teleport => {
+                self.displace(g, caster_id, cell_id);
+                return;
+            }
+            DisplacementKind::Swap => {
+                self.displace(g, caster_id, from);
+                self.displace(g, target_id, caster_position);
+                return;
+            }
+            DisplacementKind::Push(steps) => (map.direction(caster_position, from), steps),
+            DisplacementKind::Pull(steps) => {
+                let (cx, cy) = map.id_to_xy_i32(caster_position);
+                let (tx, ty) = map.id_to_xy_i32(from);
+                let max_steps = std::cmp::max((cx - tx).abs(), (cy - ty).abs()) as u32;
+                (
+                    map.direction(from, caster_position),
+                    std::cmp::min(steps, max_steps),
+                )
+            }
+        };
+        let direction = match direction {
+            Some(direction) => direction,
+            None => return,
+        };
+
+        let mut to = from;
+        let mut crossed = Vec::new();
+        // None if nothing was hit, Some(None) for the edge of the map
+        let mut collision = None;
+        let mut bumped = None;
+        for _ in 0..steps {
+            let next = match map.offset(to, direction) {
+                Some(next) => next,
+                None => {
+                    collision = Some(None);
+                    break;
+                }
+            };
+            if next == caster_position {
+                break;
+            }
+            if !map[next].is_walkable() && !map[next].is_hole() {
+                collision = Some(Some(next));
+                break;
+            }
+            if let Some((id, _)) = self.player_at(next) {
+                collision = Some(Some(next));
+                bumped = Some(*id);
+                break;
+            }
+            to = next;
+            crossed.push(next);
+            if map[to].is_hole() {
+                break;
+            }
+        }
+
+        // the cell effects of every cell crossed are triggered, and can stop the displacement
+        for cell in crossed {
+            if !self.step(g, target_id, cell, true) {
+                return;
+            }
+        }
+        self.check_exit(g, target_id);
+        if map[to].is_hole() {
+            self.kill(target_id);
+            return;
+        }
+
+        if let Some(obstacle) = collision {
+            self.log.push(GameEvent::Collided {
+                character: target_id,
+                obstacle,
+            });
+            for id in std::iter::once(target_id).chain(bumped) {
+                let character = self.characters.get(id).expect("Invalid character id");
+                let class = g.classes.get(character.class).expect("Invalid class id");
+                let damage = displacement
+                    .collision_damage
+                    .compute_damage(caster_stats, &character.effective_stats(class));
+                if damage.total() > 0 {
+                    self.apply_damage(caster_id, id, damage);
+                }
+            }
+        }
+    }
+
+    fn caster_stats(&self, g: &GameDefinition, instance: &BuffInstance) -> Stats {
+        match (
+            instance.buff.caster_stats,
+            self.characters.get(instance.source),
+        ) {
+            (CasterStats::Live, Some(caster)) => {
+                let class = g.classes.get(caster.class).expect("Invalid class id");
+                caster.effective_stats(class)
+            }
+            (CasterStats::Live, None) | (CasterStats::OnCast, _) => instance.caster_stats,
+        }
+    }
+
+    fn end_turn(&mut self) {
+        if let Some(id) = self.current {
+            let character = self.characters.get_mut(id).expect("Invalid character id");
+            for expired in character.tick_statuses() {
+                self.log.push(GameEvent::BuffExpired {
+                    target: id,
+                    effect: expired.effect,
+                });
+            }
+            self.log.push(GameEvent::TurnEnded(id));
+        }
+    }
+
+    fn player_at(&self, cell_id: Id<Cell>) -> Option<(&Id<Character>, &Character)> {
+        self.characters
+            .iter()
+            .find(|(_, character)| character.is_alive() && character.position == cell_id)
+    }
+
+    fn execute_skill(
+        &mut self,
+        curr_id: Id<Character>,
+        curr_char: Character,
+        g: &GameDefinition,
+        skill_id: Id<Skill>,
+        cell_id: Id<Cell>,
+    ) -> Result<(), Error> {
+        let skill = g.skills.get(skill_id).ok_or(Error::InvalidSkill)?;
+        let map = g.maps.get(self.map).expect("Invalid game map id");
+        if cell_id.raw() >= map.data.len() {
+            return Err(Error::CellOutOfMap);
+        }
+        let target = self.player_at(cell_id);
+
+        match skill.kind {
+            SkillKind::Magical if curr_char.has_status(Status::Silence) => {
+                return Err(Error::Silenced)
+            }
+            SkillKind::Physical if curr_char.has_status(Status::Disarm) => {
+                return Err(Error::Disarmed)
+            }
+            _ => {}
+        }
+
+        let cooldown = self.skill_cooldown(curr_id, skill_id);
+        if !skill.has_uses_left(cooldown) {
+            return Err(Error::NoUsesLeft);
+        }
+        if skill.is_on_cooldown(cooldown) {
+            return Err(Error::SkillOnCooldown);
+        }
+
+        if curr_char.current_mana < skill.cost {
+            return Err(Error::NotEnoughMana);
+        }
+
+        if !GameState::check_target(&curr_char, &target, skill.range.target) {
+            return Err(Error::InvalidTarget);
+        }
+
+        if !self.check_range(g, curr_char.position, cell_id, &skill.range, None) {
+            return Err(Error::InvalidRange);
+        }
+
+        if !self.in_line_of_sight(g, curr_char.position, cell_id, &skill.range) {
+            return Err(Error::NoLineOfSight);
+        }
+
+        let needs_free_cell = skill.effects.iter().any(|id| {
+            matches!(
+                g.effects.get(*id).expect("Invalid effect id").kind,
+                EffectKind::Displacement(Displacement {
+                    kind: DisplacementKind::Teleport,
+                    ..
+                }) | EffectKind::Summon(_)
+            )
+        });
+        if needs_free_cell {
+            if !map[cell_id].is_walkable() {
+                return Err(Error::ImpassableCell);
+            }
+            if target.is_some() {
+                return Err(Error::CellOccupied);
+            }
+        }
+
+        let class = g.classes.get(curr_char.class).expect("Invalid class id");
+        let caster_stats = curr_char.effective_stats(class);
+
+        self.characters
+            .get_mut(curr_id)
+            .expect("Invalid character id")
+            .current_mana -= skill.cost;
+        let cooldowns = self.cooldowns.entry(curr_id).or_default();
+        let cooldown = skill.use_charge(cooldowns.remove(&skill_id));
+        cooldowns.insert(skill_id, cooldown);
+        self.log.push(GameEvent::SkillCast {
+            caster: curr_id,
+            skill: skill_id,
+            target: cell_id,
+        });
+
+        // FIXME: this is a borrow-checker workaround... but probably the only actual one..?
+        let mut damage_updates = Vec::<(Id<Character>, DamageBreakdown)>::new();
+        let mut heal_updates = Vec::<(Id<Character>, i32, Overheal)>::new();
+        let mut displacement_updates = Vec::<(Id<Character>, &Displacement)>::new();
+        let mut summon_updates = Vec::<&Summon>::new();
+        let mut cell_effect_updates = Vec::<(Id<Effect>, &CellEffect)>::new();
+        let mut buff_updates = Vec::<(Id<Character>, BuffInstance)>::new();
+        let mut rng = self.rng.clone();
+
+        // They either dodge the whole skill or nothing
+        let mut hits = HashMap::<Id<Character>, bool>::new();
+        let mut misses = Vec::<Id<Character>>::new();
+        let mut is_hit = |rng: &mut Rng, id: Id<Character>, target: &Character| {
+            *hits.entry(id).or_insert_with(|| {
+                if target.team == curr_char.team {
+                    return true;
+                }
+                let hit = rng.roll(GameState::hit_chance(
+                    g,
+                    skill,
+                    &curr_char,
+                    &caster_stats,
+                    target,
+                ));
+                if !hit {
+                    misses.push(id);
+                }
+                hit
+            })
+        };
+
+        // Effects and characters are sorted so that the rolls are always done in the same order
+        let mut effects = skill.effects.iter().collect::<Vec<_>>();
+        effects.sort_by_key(|id| id.raw());
+        for effect_id in effects {
+            let effect = g.effects.get(*effect_id).expect("Invalid effect id");
+            match &effect.kind {
+                EffectKind::Buff(buff) => {
+                    for (id, target) in
+                        self.affected_characters(g, &curr_char, cell_id, effect, &buff.range)
+                    {
+                        if !is_hit(&mut rng, id, target)
+                            || !rng.roll(buff.success_rate.unwrap_or(1.0))
+                        {
+                            continue;
+                        }
+                        let instance =
+                            BuffInstance::new(*effect_id, buff, curr_id, caster_stats, self.turn);
+                        buff_updates.push((id, instance));
+                    }
+                }
+
+                EffectKind::DirectDamage(direct_damage) => {
+                    for (id, target) in self.affected_characters(
+                        g,
+                        &curr_char,
+                        cell_id,
+                        effect,
+                        &direct_damage.range,
+                    ) {
+                        if !is_hit(&mut rng, id, target) {
+                            continue;
+                        }
+                        let target_class = g.classes.get(target.class).expect("Invalid class id");
+                        let damage = direct_damage
+                            .damage
+                            .compute_damage(&caster_stats, &target.effective_stats(target_class));
+                        damage_updates.push((id, damage));
+                    }
+                }
+
+                EffectKind::Heal(direct_heal) => {
+                    for (id, target) in
+                        self.affected_characters(g, &curr_char, cell_id, effect, &direct_heal.range)
+                    {
+                        if !is_hit(&mut rng, id, target) {
+                            continue;
+                        }
+                        let target_class = g.classes.get(target.class).expect("Invalid class id");
+                        let amount = direct_heal
+                            .heal
+                            .compute_heal(&caster_stats, &target.effective_stats(target_class));
+                        heal_updates.push((id, amount, direct_heal.heal.overheal));
+                    }
+                }
+
+                EffectKind::Displacement(displacement) => {
+                    if let DisplacementKind::Teleport = displacement.kind {
+                        displacement_updates.push((curr_id, displacement));
+                        continue;
+                    }
+                    for (id, target) in self.affected_characters(
+                        g,
+                        &curr_char,
+                        cell_id,
+                        effect,
+                        &displacement.range,
+                    ) {
+                        if id == curr_id || !is_hit(&mut rng, id, target) {
+                            continue;
+                        }
+                        displacement_updates.push((id, displacement));
+                    }
+                }
+
+                EffectKind::Summon(summon) => summon_updates.push(summon),
+
+                EffectKind::CellEffect(cell_effect) => {
+                    cell_effect_updates.push((*effect_id, cell_effect))
+                }
+            }
+        }
+        self.rng = rng;
+
+        for id in misses {
+            self.log.push(GameEvent::Missed {
+                caster: Some(curr_id),
+                target: id,
+            });
+        }
+
+        for (id, damage) in damage_updates {
+            self.apply_damage(curr_id, id, damage);
+        }
+
+        for (id, amount, overheal) in heal_updates {
+            self.apply_heal(g, curr_id, id, amount, overheal);
+        }
+
+        for (id, displacement) in displacement_updates {
+            self.apply_displacement(g, curr_id, &caster_stats, id, cell_id, displacement);
+        }
+
+        for summon in summon_updates {
+            self.summon(g, curr_id, cell_id, summon);
+        }
+
+        for (effect, cell_effect) in cell_effect_updates {
+            let cells = self.area_cells(g, curr_char.position, cell_id, &cell_effect.range);
+            for cell in &cells {
+                let instance = CellEffectInstance::new(
+                    effect,
+                    cell_effect,
+                    curr_id,
+                    curr_char.team,
+                    caster_stats,
+                );
+                self.cell_effects.entry(*cell).or_default().push(instance);
+            }
+            self.log.push(GameEvent::CellEffectCreated {
+                source: curr_id,
+                effect,
+                cells,
+            });
+        }
+
+        for (id, buff) in buff_updates {
+            let character = self.characters.get_mut(id).expect("Invalid character id");
+            let effect = buff.effect;
+            if character.is_alive() && character.add_buff(buff) {
+                self.log.push(GameEvent::BuffApplied {
+                    source: Some(curr_id),
+                    target: id,
+                    effect,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Only enemies can dodge
+    fn hit_chance(
+        g: &GameDefinition,
+        skill: &Skill,
+        caster: &Character,
+        caster_stats: &Stats,
+        target: &Character,
+    ) -> f32 {
+        if target.team == caster.team {
+            return 1.0;
+        }
+        let target_class = g.classes.get(target.class).expect("Invalid class id");
+        skill.hit_chance(caster_stats, &target.effective_stats(target_class))
+    }
+
+    /// Cells hit by an effect cast from `from` and landing on the given cell. Directional areas
+    /// face away from the caster.
+    pub fn area_cells(
+        &self,
+        g: &GameDefinition,
+        from: Id<Cell>,
+        cell_id: Id<Cell>,
+        area: &Option<Range>,
+    ) -> Vec<Id<Cell>> {
+        match area {
+            None => vec![cell_id],
+            Some(area) => {
+                let map = g.maps.get(self.map).expect("Invalid game map id");
+                map.shape(cell_id, area, map.direction(from, cell_id))
+                    .into_iter()
+                    .filter(|c| self.in_line_of_sight(g, cell_id, *c, area))
+                    .collect()
+            }
+        }
+    }
+
+    /// Cells the given character can cast the skill on from where they stand, e.g. to highlight
+    /// them. Targets are not checked as they may move before the skill is cast.
+    pub fn range_cells(
+        &self,
+        g: &GameDefinition,
+        character_id: Id<Character>,
+        skill_id: Id<Skill>,
+    ) -> Vec<Id<Cell>> {
+        let character = self
+            .characters
+            .get(character_id)
+            .expect("Invalid character id");
+        let skill = g.skills.get(skill_id).expect("Invalid skill id");
+        let map = g.maps.get(self.map).expect("Invalid game map id");
+        map.shape(character.position, &skill.range, None)
+            .into_iter()
+            .filter(|c| self.in_line_of_sight(g, character.position, *c, &skill.range))
+            .collect()
+    }
+
+    /// Characters affected by an effect hitting the given cell: either the one standing on the
+    /// cell, or every valid target within the area of effect around it (which must be in line
+    /// of sight of the impact point, if required). Both are filtered by the target of the effect.
+    fn affected_characters(
+        &self,
+        g: &GameDefinition,
+        caster: &Character,
+        cell_id: Id<Cell>,
+        effect: &Effect,
+        area: &Option<Range>,
+    ) -> Vec<(Id<Character>, &Character)> {
+        let is_effect_target = |id: &Id<Character>, character: &Character| {
+            effect.target.is_none_or(|target| {
+                GameState::check_target(caster, &Some((id, character)), target)
+            })
+        };
+        match area {
+            None => self
+                .player_at(cell_id)
+                .filter(|(id, character)| is_effect_target(id, character))
+                .map(|(id, character)| (*id, character))
+                .into_iter()
+                .collect(),
+            Some(area) => {
+                let facing = g
+                    .maps
+                    .get(self.map)
+                    .expect("Invalid game map id")
+                    .direction(caster.position, cell_id);
+                let mut characters = self
+                    .characters
+                    .iter()
+                    .filter(|(id, character)| {
+                        character.is_alive()
+                            && GameState::check_target(caster, &Some((id, character)), area.target)
+                            && is_effect_target(id, character)
+                            && self.check_range(g, cell_id, character.position, area, facing)
+                            && self.in_line_of_sight(g, cell_id, character.position, area)
+                    })
+                    .map(|(id, character)| (*id, character))
+                    .collect::<Vec<_>>();
+                characters.sort_by_key(|(id, _)| id.raw());
+                characters
+            }
+        }
+    }
+
+    /// Checks if a skill with the given range can reach `to` from `from`, regardless of the
+    /// distance between both cells
+    pub fn in_line_of_sight(
+        &self,
+        g: &GameDefinition,
+        from: Id<Cell>,
+        to: Id<Cell>,
+        range: &Range,
+    ) -> bool {
+        if !range.needs_los {
+            return true;
+        }
+
+        let blockers = if range.blocked_by_characters {
+            self.characters
+                .iter()
+                .filter(|(_, character)| character.is_alive())
+                .map(|(_, character)| character.position)
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        g.maps
+            .get(self.map)
+            .expect("Invalid game map id")
+            .has_line_of_sight(from, to, &blockers)
+    }
+
+    fn check_target(
+        attacker: &Character,
+        target_opt: &Option<(&Id<Character>, &Character)>,
+        target_kind: Target,
+    ) -> bool {
+        if let Some((_, target)) = target_opt {
+            match (target_kind, target.team == attacker.team) {
+                (Target::Anything, _) | (Target::Anyone, _) => true,
+                (Target::Enemy, is_same_team) => !is_same_team,
+                (Target::Ally, is_same_team) => is_same_team,
+            }
+        } else {
+            Target::Anything == target_kind
+        }
+    }
+
+    fn check_range(
+        &self,
+        g: &GameDefinition,
+        start: Id<Cell>,
+        end: Id<Cell>,
+        range: &Range,
+        facing: Option<(i32, i32)>,
+    ) -> bool {
+        g.maps
+            .get(self.map)
+            .expect("Invalid game map id")
+            .in_shape(start, end, range, facing)
+    }
+
+    /// Cells occupied by the other characters, from the point of view of the given one
+    pub fn occupied_cells(&self, character_id: Id<Character>) -> Occupied {
+        let team = self
+            .characters
+            .get(character_id)
+            .expect("Invalid character id")
+            .team;
+
+        let mut occupied = Occupied::default();
+        for (id, character) in self.characters.iter() {
+            if *id == character_id || !character.is_alive() {
+                continue;
+            }
+            if self.rules.pass_through_allies && character.team == team {
+                occupied.passable.insert(character.position);
+            } else {
+                occupied.blocking.insert(character.position);
+            }
+        }
+        occupied
+    }
+
+    /// None if the character never used the skill i.e. every charge is available
+    pub fn skill_cooldown(
+        &self,
+        character_id: Id<Character>,
+        skill_id: Id<Skill>,
+    ) -> Option<&SkillCooldown> {
+        self.cooldowns
+            .get(&character_id)
+            .and_then(|cooldowns| cooldowns.get(&skill_id))
+    }
+
+    /// Cell effects the given team knows about
+    pub fn visible_cell_effects(&self, team: Id<Team>) -> Vec<(Id<Cell>, &CellEffectInstance)> {
+        let mut cell_effects = self
+            .cell_effects
+            .iter()
+            .flat_map(|(cell, instances)| instances.iter().map(move |instance| (*cell, instance)))
+            .filter(|(_, instance)| instance.is_visible_to(team))
+            .collect::<Vec<_>>();
+        cell_effects.sort_by_key(|(cell, _)| cell.raw());
+        cell_effects
+    }
+
+    /// Cells seen by at least one of the living characters of the team
+    pub fn visible_cells(&self, g: &GameDefinition, team: Id<Team>) -> HashSet<Id<Cell>> {
+        let map = g.maps.get(self.map).expect("Invalid game map id");
+        self.characters
+            .iter()
+            .filter(|(_, character)| character.is_alive() && character.team == team)
+            .flat_map(|(_, character)| {
+                let class = g.classes.get(character.class).expect("Invalid class id");
+                let vision = std::cmp::max(0, character.effective_vision(class)) as u32;
+                map.visible_cells(character.position, vision)
+            })
+            .collect()
+    }
+
+    /// Cells seen by the team, and characters it knows about i.e. its own ones and the ones
+    /// standing in sight. Without a team (i.e. for spectators), nothing is known.
+    fn known_by(
+        &self,
+        g: &GameDefinition,
+        team: Option<Id<Team>>,
+    ) -> (HashSet<Id<Cell>>, HashSet<Id<Character>>) {
+        let visible = team.map_or_else(HashSet::new, |team| self.visible_cells(g, team));
+        let known = self
+            .characters
+            .iter()
+            .filter(|(_, character)| {
+                Some(character.team) == team || visible.contains(&character.position)
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        (visible, known)
+    }
+
+    /// Copy of the game state restricted to what the given team knows, to be sent to its players.
+    /// Characters of the other teams standing out of sight are removed (along with their
+    /// initiative, cooldowns and turn if they are playing), as are the buffs hidden by the other
+    /// teams and the cell effects either hidden by the other teams or out of sight. Without a
+    /// team (i.e. for spectators), only the map is known.
+    /// The log, which would reveal past positions, is dropped and the random generator is reset
+    /// so that the upcoming rolls cannot be predicted: the view can only be displayed, not
+    /// played (nor used to compute the outcome).
+    pub fn view_for(&self, g: &GameDefinition, team: Option<Id<Team>>) -> GameState {
+        let (visible, known) = self.known_by(g, team);
+        let teams = self
+            .characters
+            .iter()
+            .map(|(id, character)| (*id, character.team))
+            .collect::<HashMap<_, _>>();
+
+        let mut view = self.clone();
+        view.characters.retain(|id, _| known.contains(id));
+        for (_, character) in view.characters.iter_mut() {
+            character.buffs.retain(|instance| {
+                !instance.buff.hidden || teams.get(&instance.source).copied() == team
+            });
+        }
+
+        view.current = self.current.filter(|id| known.contains(id));
+        view.initiative.retain(|id, _| known.contains(id));
+        view.played_this_round.retain(|id| known.contains(id));
+        view.cooldowns.retain(|id, _| known.contains(id));
+        view.cell_effects.retain(|cell, instances| {
+            instances.retain(|instance| {
+                Some(instance.team) == team
+                    || !instance.cell_effect.hidden && visible.contains(cell)
+            });
+            !instances.is_empty()
+        });
+
+        view.log.clear();
+        view.rng = Rng::new(0);
+        view
+    }
+
+    /// Events (e.g. the result of an action) restricted to what the given team knows, once they
+    /// happened: see `view_for`. Events involving characters out of sight are dropped, as are
+    /// the ones about the buffs and cell effects hidden by the other teams. The events affecting
+    /// a character in sight are kept, with their source removed if it is out of sight e.g. for
+    /// damage dealt by a hidden enemy.
+    pub fn visible_events(
+        &self,
+        g: &GameDefinition,
+        team: Option<Id<Team>>,
+        events: Vec<GameEvent>,
+    ) -> Vec<GameEvent> {
+        let (visible, known) = self.known_by(g, team);
+        let is_own = |id: &Id<Character>| {
+            self.characters
+                .get(*id)
+                .is_some_and(|character| Some(character.team) == team)
+        };
+        let is_hidden = |effect: &Id<Effect>| match g.effects.get(*effect).map(|e| &e.kind) {
+            Some(EffectKind::Buff(buff)) => buff.hidden,
+            Some(EffectKind::CellEffect(cell_effect)) => cell_effect.hidden,
+            _ => false,
+        };
+        let redact = |source: Option<Id<Character>>| source.filter(|id| known.contains(id));
+
+        events
+            .into_iter()
+            .filter_map(|event| {
+                let is_visible = match &event {
+                    GameEvent::RoundStarted(_)
+                    | GameEvent::ZoneCaptured { .. }
+                    | GameEvent::Scored { .. }
+                    | GameEvent::GameOver(_) => true,
+                    GameEvent::TurnStarted(id)
+                    | GameEvent::TurnEnded(id)
+                    | GameEvent::TurnSkipped(id)
+                    | GameEvent::Died(id)
+                    | GameEvent::ExitReached(id)
+                    | GameEvent::Moved { character: id, .. }
+                    | GameEvent::Displaced { character: id, .. }
+                    | GameEvent::Collided { character: id, .. }
+                    | GameEvent::SkillCast { caster: id, .. }
+                    | GameEvent::CellEffectTriggered { character: id, .. }
+                    | GameEvent::Missed { target: id, .. }
+                    | GameEvent::Damaged { target: id, .. }
+                    | GameEvent::Healed { target: id, .. } => known.contains(id),
+                    GameEvent::Summoned { owner, summon, .. } => {
+                        known.contains(owner) && known.contains(summon)
+                    }
+                    GameEvent::Absorbed {
+                        source,
+                        target,
+                        effect,
+                        ..
+                    }
+                    | GameEvent::BuffApplied {
+                        source,
+                        target,
+                        effect,
+                    } => {
+                        known.contains(target)
+                            && (!is_hidden(effect) || source.as_ref().is_some_and(is_own))
+                    }
+                    // the source of the buff is unknown at this point
+                    GameEvent::BuffExpired { target, effect } => {
+                        known.contains(target) && !is_hidden(effect)
+                    }
+                    GameEvent::CellEffectCreated { source, effect, .. } => {
+                        is_own(source) || known.contains(source) && !is_hidden(effect)
+                    }
+                    // the summon is gone, only its last position can tell whether it was in sight
+                    GameEvent::SummonExpired { owner, cell, .. } => {
+                        is_own(owner) || visible.contains(cell)
+                    }
+                    GameEvent::CellEffectExpired { cell, effect } => {
+                        visible.contains(cell) && !is_hidden(effect)
+                    }
+                };
+                if !is_visible {
+                    return None;
+                }
+
+                match event {
+                    GameEvent::Missed { caster, target } => Some(GameEvent::Missed {
+                        caster: redact(caster),
+                        target,
+                    }),
+                    GameEvent::Damaged {
+                        source,
+                        target,
+                        amount,
+                        absorbed,
+                        breakdown,
+                    } => Some(GameEvent::Damaged {
+                        source: redact(source),
+                        target,
+                        amount,
+                        absorbed,
+                        breakdown,
+                    }),
+                    GameEvent::Absorbed {
+                        source,
+                        target,
+                        effect,
+                        amount,
+                    } => Some(GameEvent::Absorbed {
+                        source: redact(source),
+                        target,
+                        effect,
+                        amount,
+                    }),
+                    GameEvent::Healed {
+                        source,
+                        target,
+                        amount,
+                        shield,
+                    } => Some(GameEvent::Healed {
+                        source: redact(source),
+                        target,
+                        amount,
+                        shield,
+                    }),
+                    GameEvent::BuffApplied {
+                        source,
+                        target,
+                        effect,
+                    } => Some(GameEvent::BuffApplied {
+                        source: redact(source),
+                        target,
+                        effect,
+                    }),
+                    // only the cells in sight are revealed for the effects of the other teams
+                    GameEvent::CellEffectCreated {
+                        source,
+                        effect,
+                        cells,
+                    } if !is_own(&source) => {
+                        let cells = cells
+                            .into_iter()
+                            .filter(|cell| visible.contains(cell))
+                            .collect::<Vec<_>>();
+                        (!cells.is_empty()).then_some(GameEvent::CellEffectCreated {
+                            source,
+                            effect,
+                            cells,
+                        })
+                    }
+                    event => Some(event),
+                }
+            })
+            .collect()
+    }
+
+    /// Cells the character can currently move to, with the movement cost to get there
+    pub fn reachable_cells(
+        &self,
+        g: &GameDefinition,
+        character_id: Id<Character>,
+    ) -> HashMap<Id<Cell>, u32> {
+        let character = self
+            .characters
+            .get(character_id)
+            .expect("Invalid character id");
+        let class = g.classes.get(character.class).expect("Invalid class id");
+        let budget = std::cmp::max(0, character.effective_swiftness(class)) as u32;
+
+        g.maps
+            .get(self.map)
+            .expect("Invalid game map id")
+            .reachable_cells(
+                character.position,
+                budget,
+                &self.occupied_cells(character_id),
+            )
+    }
+
+    /// Moves the character within the given movement budget, returns the cost of the path
+    fn execute_move(
+        &mut self,
+        curr_id: Id<Character>,
+        curr_char: Character,
+        g: &GameDefinition,
+        cell_id: Id<Cell>,
+        budget: i32,
+    ) -> Result<u32, Error> {
+        if curr_char.has_status(Status::Root) {
+            return Err(Error::Rooted);
+        }
+
+        let map = g.maps.get(self.map).expect("Invalid game map id");
+        if cell_id.raw() >= map.data.len() || !map[cell_id].is_walkable() {
+            return Err(Error::ImpassableCell);
+        }
+
+        let occupied = self.occupied_cells(curr_id);
+        if occupied.contains(cell_id) {
+            return Err(Error::CellOccupied);
+        }
+
+        let cost = if budget < 0 {
+            None
+        } else {
+            map.reachable_cells(curr_char.position, budget as u32, &occupied)
+                .get(&cell_id)
+                .cloned()
+        };
+        match cost {
+            // the cost of the whole path is paid even if the character is stopped on the way, as
+            // they are either dead or rooted
+            Some(cost) => {
+                let path = map
+                    .find_path(curr_char.position, cell_id, cost, &occupied)
+                    .expect("Cell is reachable");
+                for cell in path.into_iter().skip(1) {
+                    if !self.step(g, curr_id, cell, false) {
+                        return Ok(cost);
+                    }
+                }
+                self.check_exit(g, curr_id);
+                Ok(cost)
+            }
+            None => Err(Error::MoveCellTooFar),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        Action, ActionPreview, GameState, Outcome, Rules, TargetPreview, TurnRules, TurnState,
+    };
+    use crate::game::character::{Character, CharacterMapBuilder};
+    use crate::game::damage::DamageBreakdown;
+    use crate::game::effect::{EffectKind, Target};
+    use crate::game::error::Error;
+    use crate::game::event::GameEvent;
+    use crate::game::game_definition::GameDefinition;
+    use crate::game::id_map::{Id, IdMap, IdMapBuilder};
+    use crate::game::mode::{Capture, GameMode};
+    use crate::game::skill::SkillCooldown;
+    use serde_json::json;
+
+    // Three classes, sharing the same skills:
+    // 0. flat pure damage
+    // 1. pure DoT (refreshed)
+    // 2. pure DoT (stacked)
+    // 3. health buff
+    // 4. flat pure damage to enemies around the target cell
+    // 5. same as 0, but physical and always missing
+    // 6. same as 0, but costing 600 mana
+    // 7. lethal flat pure damage
+    // 8. flat heal
+    // 9. flat heal, converting the overheal into a shield
+    // 10. HoT
+    // 11. push by 3 cells, with flat pure collision damage
+    // 12. pull by 5 cells
+    // 13. teleport
+    // 14. swap
+    // 15. summon a turret, casting skill 0 and lasting for 2 turns
+    // 16. summon an inert totem
+    // 17. fire field applying the DoT of skill 1 for 2 turns, around the target cell
+    // 18. hidden trap dealing 100 pure damage to enemies
+    // 19. same as 0, with a cooldown of 1
+    // 20. same as 19, with 2 charges
+    // 21. same as 0, usable once per game
+    // 22-25. stun, root, silence and disarm for one turn
+    // 26. shield absorbing 150 damage of any type
+    // 27. shield absorbing 500 physical damage
+    // 28. flat physical damage (always hitting)
+    // The last class is used for the summons.
+    fn game_definition() -> GameDefinition {
+        const SKILL_COUNT: usize = 29;
+        let class = |name: &str, swiftness: i32| {
+            json!({
+                "name": name,
+                "description": "",
+                "health": 1000,
+                "mana": 1000,
+                "swiftness": swiftness,
+                "strength": 100,
+                "dexterity": 100,
+                "armor": 0,
+                "intelligence": 100,
+                "concentration": 100,
+                "willpower": 0,
+            })
+        };
+        let range = json!({
+            "min": 0,
+            "max": 10,
+            "kind": "Star",
+            "target": "Anyone",
+            "needs_los": false,
+        });
+        let skill = |effect: usize| {
+            json!({
+                "name": "",
+                "kind": "Magical",
+                "cost": 0,
+                "range": range,
+                "effects": [effect],
+            })
+        };
+        let dot = |stacking: &str| {
+            json!({ "Buff": {
+                "name": "dot",
+                "duration": 3,
+                "kind": { "DoT": { "flat_pure": 10 } },
+                "stacking": stacking,
+            }})
+        };
+
+        let status = |status: &str| {
+            json!({ "Buff": {
+                "name": status,
+                "duration": 1,
+                "kind": { "Status": status },
+            }})
+        };
+        let with = |fields: &[(&str, u32)]| {
+            let mut skill = skill(0);
+            for (field, value) in fields {
+                skill[*field] = json!(value);
+            }
+            skill
+        };
+        let mut summon = class("summon", 10);
+        summon["summon"] = json!(true);
+        let anywhere_skill = |effect: usize| {
+            json!({
+                "name": "",
+                "kind": "Magical",
+                "cost": 0,
+                "range": { "min": 0, "max": 10, "kind": "Star", "target": "Anything", "needs_los": false },
+                "effects": [effect],
+            })
+        };
+
+        serde_json::from_value(json!({
+            "classes": {
+                "0": class("slow", 10),
+                "1": class("fast", 11),
+                "2": class("sprinter", 22),
+                "3": summon,
+            },
+            "skills": {
+                "0": skill(0),
+                "1": skill(1),
+                "2": skill(2),
+                "3": skill(3),
+                "4": {
+                    "name": "",
+                    "kind": "Magical",
+                    "cost": 0,
+                    "range": { "min": 0, "max": 10, "kind": "Star", "target": "Anything", "needs_los": false },
+                    "effects": [4],
+                },
+                "5": {
+                    "name": "",
+                    "kind": "Physical",
+                    "cost": 0,
+                    "range": range,
+                    "precision": 0.0,
+                    "effects": [0],
+                },
+                "6": {
+                    "name": "",
+                    "kind": "Magical",
+                    "cost": 600,
+                    "range": range,
+                    "effects": [0],
+                },
+                "7": skill(5),
+                "8": skill(6),
+                "9": skill(7),
+                "10": skill(8),
+                "11": skill(9),
+                "12": skill(10),
+                "13": {
+                    "name": "",
+                    "kind": "Magical",
+                    "cost": 0,
+                    "range": { "min": 0, "max": 10, "kind": "Star", "target": "Anything", "needs_los": false },
+                    "effects": [11],
+                },
+                "14": skill(12),
+                "15": anywhere_skill(13),
+                "16": anywhere_skill(14),
+                "17": anywhere_skill(15),
+                "18": anywhere_skill(16),
+                "19": with(&[("cooldown", 1)]),
+                "20": with(&[("cooldown", 1), ("charges", 2)]),
+                "21": with(&[("uses_per_game", 1)]),
+                "22": skill(17),
+                "23": skill(18),
+                "24": skill(19),
+                "25": skill(20),
+                "26": skill(21),
+                "27": skill(22),
+                "28": skill(23),
+            },
+            "effects": {
+                "0": { "id": 0, "kind": { "DirectDamage": { "damage": { "flat_pure": 100 } } } },
+                "1": { "id": 1, "kind": dot("Refresh") },
+                "2": { "id": 2, "kind": dot("Stack") },
+                "3": { "id": 3, "kind": { "Buff": {
+                    "name": "fortify",
+                    "duration": 1,
+                    "kind": { "Attribute": ["Health", { "Perc": 0.5 }] },
+                }}},
+                "4": { "id": 4, "kind": { "DirectDamage": {
+                    "range": { "min": 0, "max": 1, "kind": "Star", "target": "Enemy", "needs_los": true },
+                    "damage": { "flat_pure": 100 },
+                }}},
+                "5": { "id": 5, "kind": { "DirectDamage": { "damage": { "flat_pure": 1000 } } } },
+                "6": { "id": 6, "kind": { "Heal": { "heal": { "flat": 300 } } } },
+                "7": { "id": 7, "kind": { "Heal": { "heal": { "flat": 300, "overheal": "Shield" } } } },
+                "8": { "id": 8, "kind": { "Buff": {
+                    "name": "hot",
+                    "duration": 3,
+                    "kind": { "HoT": { "perc_max_health": 0.1 } },
+                }}},
+                "9": { "id": 9, "kind": { "Displacement": {
+                    "kind": { "Push": 3 },
+                    "collision_damage": { "flat_pure": 50 },
+                }}},
+                "10": { "id": 10, "kind": { "Displacement": { "kind": { "Pull": 5 } } } },
+                "11": { "id": 11, "kind": { "Displacement": { "kind": "Teleport" } } },
+                "12": { "id": 12, "kind": { "Displacement": { "kind": "Swap" } } },
+                "13": { "id": 13, "kind": { "Summon": { "class": 3, "behaviour": "Attack", "lifetime": 2 } } },
+                "14": { "id": 14, "kind": { "Summon": { "class": 3, "lifetime": null } } },
+                "15": { "id": 15, "kind": { "CellEffect": {
+                    "range": { "min": 0, "max": 1, "kind": "Star", "target": "Anything", "needs_los": false },
+                    "effects": [1],
+                    "triggers": ["TurnStart", "Enter"],
+                    "target": "Anyone",
+                    "duration": 2,
+                }}},
+                "16": { "id": 16, "kind": { "CellEffect": {
+                    "range": null,
+                    "effects": [0],
+                    "triggers": ["Enter"],
+                    "target": "Enemy",
+                    "duration": 10,
+                    "hidden": true,
+                    "single_use": true,
+                }}},
+                "17": { "id": 17, "kind": status("Stun") },
+                "18": { "id": 18, "kind": status("Root") },
+                "19": { "id": 19, "kind": status("Silence") },
+                "20": { "id": 20, "kind": status("Disarm") },
+                "21": { "id": 21, "kind": { "Buff": {
+                    "name": "shield",
+                    "duration": 3,
+                    "kind": { "Shield": { "flat": 150 } },
+                }}},
+                "22": { "id": 22, "kind": { "Buff": {
+                    "name": "armor",
+                    "duration": 3,
+                    "kind": { "Shield": { "flat": 500, "kind": "Physical" } },
+                }}},
+                "23": { "id": 23, "kind": { "DirectDamage": { "damage": { "flat_physical": 100 } } } },
+            },
+            "maps": { "0": {
+                "name": "",
+                "width": 12,
+                "height": 5,
+                "teams": [["first", [0]], ["second", [4]]],
+                "data": vec![json!({ "height": 0, "attribute": "None" }); 60],
+            }},
+            "class_to_skills": {
+                "0": (0..SKILL_COUNT).collect::<Vec<_>>(),
+                "1": (0..SKILL_COUNT).collect::<Vec<_>>(),
+                "2": (0..SKILL_COUNT).collect::<Vec<_>>(),
+                "3": [0],
+            },
+            "skill_to_classes": (0..SKILL_COUNT)
+                .map(|id| (id.to_string(), json!([0, 1, 2])))
+                .collect::<serde_json::Map<_, _>>(),
+        }))
+        .unwrap()
+    }
+
+    // The "fast" character (id 1) plays first, then turns alternate for a while
+    fn game_state(g: &GameDefinition) -> GameState {
+        game_state_with(g, &[(0, 0, 0), (1, 4, 1)])
+    }
+
+    fn game_state_with(g: &GameDefinition, characters_def: &[(usize, usize, usize)]) -> GameState {
+        game_state_with_rules(g, characters_def, Default::default())
+    }
+
+    fn characters(
+        g: &GameDefinition,
+        characters_def: &[(usize, usize, usize)],
+    ) -> IdMap<Character> {
+        let mut characters = IdMapBuilder::new();
+        for &(class, cell, team) in characters_def {
+            let class_id = Id::new(class);
+            characters.add(Character::new(
+                class_id,
+                Id::new(cell),
+                g.classes.get(class_id).unwrap(),
+                "",
+                Id::new(team),
+            ));
+        }
+        characters.build()
+    }
+
+    fn game_state_with_rules(
+        g: &GameDefinition,
+        characters_def: &[(usize, usize, usize)],
+        rules: Rules,
+    ) -> GameState {
+        GameState::new(g, characters(g, characters_def), Id::new(0), rules, 0)
+    }
+
+    fn set_cell(g: &mut GameDefinition, cell: usize, attribute: &str) {
+        let map = g.maps.get_mut(Id::new(0)).unwrap();
+        map.data[cell] =
+            serde_json::from_value(json!({ "height": 0, "attribute": attribute })).unwrap();
+    }
+
+    fn position(gs: &GameState, id: usize) -> usize {
+        gs.characters.get(Id::new(id)).unwrap().position.raw()
+    }
+
+    fn health(gs: &GameState, id: usize) -> i32 {
+        gs.characters.get(Id::new(id)).unwrap().current_health
+    }
+
+    #[test]
+    fn test_direct_damage() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        assert_eq!(gs.player_to_play(), Id::new(1));
+        gs.next_action(&g, Action::Skill(Id::new(0), Id::new(0)))
+            .unwrap();
+        assert_eq!(health(&gs, 0), 900);
+        assert_eq!(gs.player_to_play(), Id::new(0));
+    }
+
+    #[test]
+    fn test_dot_ticks_and_expires() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        gs.next_action(&g, Action::Skill(Id::new(1), Id::new(0)))
+            .unwrap();
+        let instance = &gs.characters.get(Id::new(0)).unwrap().buffs[0];
+        assert_eq!(instance.source, Id::new(1));
+        assert_eq!(instance.cast_turn, 1);
+        assert_eq!(instance.caster_stats.health, 1000);
+
+        // ticks at the start of the afflicted character's turn
+        assert_eq!(health(&gs, 0), 990);
+        for expected in [980, 970, 970] {
+            gs.next_action(&g, Action::Pass).unwrap();
+            gs.next_action(&g, Action::Pass).unwrap();
+            assert_eq!(health(&gs, 0), expected);
+        }
+        assert!(gs.characters.get(Id::new(0)).unwrap().buffs.is_empty());
+    }
+
+    #[test]
+    fn test_buff_stacking() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        // refreshed buffs are only applied once
+        for _ in 0..2 {
+            gs.next_action(&g, Action::Skill(Id::new(1), Id::new(0)))
+                .unwrap();
+            gs.next_action(&g, Action::Pass).unwrap();
+        }
+        assert_eq!(gs.characters.get(Id::new(0)).unwrap().buffs.len(), 1);
+
+        // stacked ones are tracked separately
+        gs.next_action(&g, Action::Skill(Id::new(2), Id::new(0)))
+            .unwrap();
+        gs.next_action(&g, Action::Pass).unwrap();
+        gs.next_action(&g, Action::Skill(Id::new(2), Id::new(0)))
+            .unwrap();
+        assert_eq!(gs.characters.get(Id::new(0)).unwrap().buffs.len(), 2);
+    }
+
+    #[test]
+    fn test_attribute_buff() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+        let class = g.classes.get(Id::new(1)).unwrap();
+
+        gs.next_action(&g, Action::Skill(Id::new(3), Id::new(4)))
+            .unwrap();
+        let character = gs.characters.get(Id::new(1)).unwrap();
+        assert_eq!(character.effective_health(class), 1500);
+
+        // lasts until the start of the next turn of the character
+        gs.next_action(&g, Action::Pass).unwrap();
+        let character = gs.characters.get(Id::new(1)).unwrap();
+        assert_eq!(character.effective_health(class), 1000);
+    }
+
+    #[test]
+    fn test_heal() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        gs.next_action(&g, Action::Skill(Id::new(0), Id::new(0)))
+            .unwrap();
+        assert_eq!(health(&gs, 0), 900);
+
+        // overheal is discarded
+        let events = gs
+            .next_action(&g, Action::Skill(Id::new(8), Id::new(0)))
+            .unwrap();
+        assert_eq!(
+            events[1],
+            GameEvent::Healed {
+                source: Some(Id::new(0)),
+                target: Id::new(0),
+                amount: 100,
+                shield: 0,
+            }
+        );
+        assert_eq!(health(&gs, 0), 1000);
+        assert_eq!(gs.characters.get(Id::new(0)).unwrap().shield, 0);
+
+        // or converted to a shield, absorbing damage before the health
+        gs.next_action(&g, Action::Skill(Id::new(9), Id::new(4)))
+            .unwrap();
+        assert_eq!(health(&gs, 1), 1000);
+        assert_eq!(gs.characters.get(Id::new(1)).unwrap().shield, 300);
+        let events = gs
+            .next_action(&g, Action::Skill(Id::new(0), Id::new(4)))
+            .unwrap();
+        assert!(matches!(
+            events[1],
+            GameEvent::Damaged {
+                amount: 0,
+                absorbed: 100,
+                ..
+            }
+        ));
+        assert_eq!(health(&gs, 1), 1000);
+        assert_eq!(gs.characters.get(Id::new(1)).unwrap().shield, 200);
+    }
+
+    #[test]
+    fn test_hot_and_clamping() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        // fast character buffs their health, then gets a HoT from the slow one
+        gs.next_action(&g, Action::Skill(Id::new(3), Id::new(4)))
+            .unwrap();
+        // at the start of their next turn, the HoT heals 10% of the buffed maximum health, then the
+        // health buff expires
+        gs.next_action(&g, Action::Skill(Id::new(10), Id::new(4)))
+            .unwrap();
+        assert!(gs.log.contains(&GameEvent::Healed {
+            source: Some(Id::new(0)),
+            target: Id::new(1),
+            amount: 150,
+            shield: 0,
+        }));
+        assert_eq!(health(&gs, 1), 1000);
+    }
+
+    #[test]
+    fn test_push() {
+        let mut g = game_definition();
+        set_cell(&mut g, 9, "Wall");
+        let mut gs = game_state(&g);
+
+        // the slow character is already on the edge of the map
+        let events = gs
+            .next_action(&g, Action::Skill(Id::new(11), Id::new(0)))
+            .unwrap();
+        assert_eq!(
+            events[1],
+            GameEvent::Collided {
+                character: Id::new(0),
+                obstacle: None,
+            }
+        );
+        assert_eq!(position(&gs, 0), 0);
+        assert_eq!(health(&gs, 0), 950);
+
+        gs.next_action(&g, Action::Skill(Id::new(11), Id::new(4)))
+            .unwrap();
+        assert_eq!(position(&gs, 1), 7);
+        assert_eq!(health(&gs, 1), 1000);
+
+        // stopped by the wall
+        gs.next_action(&g, Action::Pass).unwrap();
+        let events = gs
+            .next_action(&g, Action::Skill(Id::new(11), Id::new(7)))
+            .unwrap();
+        assert_eq!(
+            events[2],
+            GameEvent::Collided {
+                character: Id::new(1),
+                obstacle: Some(Id::new(9)),
+            }
+        );
+        assert_eq!(position(&gs, 1), 8);
+        assert_eq!(health(&gs, 1), 950);
+    }
+
+    #[test]
+    fn test_push_into_character_and_hole() {
+        let mut g = game_definition();
+        set_cell(&mut g, 6, "Hole");
+        let mut gs = game_state_with(&g, &[(0, 0, 0), (0, 1, 0), (1, 4, 1)]);
+
+        // both characters take the collision damage
+        gs.next_action(&g, Action::Skill(Id::new(11), Id::new(1)))
+            .unwrap();
+        assert_eq!(position(&gs, 1), 1);
+        assert_eq!(health(&gs, 0), 950);
+        assert_eq!(health(&gs, 1), 950);
+
+        gs.next_action(&g, Action::Skill(Id::new(11), Id::new(4)))
+            .unwrap();
+        assert_eq!(position(&gs, 2), 6);
+        assert!(gs.log.contains(&GameEvent::Died(Id::new(2))));
+        assert_eq!(gs.outcome(), Outcome::Victory(Id::new(0)));
+    }
+
+    #[test]
+    fn test_pull_teleport_swap() {
+        let mut g = game_definition();
+        set_cell(&mut g, 20, "Wall");
+        let mut gs = game_state(&g);
+
+        // stops next to the caster
+        gs.next_action(&g, Action::Skill(Id::new(12), Id::new(0)))
+            .unwrap();
+        assert_eq!(position(&gs, 0), 3);
+
+        gs.next_action(&g, Action::Pass).unwrap();
+        assert!(matches!(
+            gs.next_action(&g, Action::Skill(Id::new(13), Id::new(3))),
+            Err(Error::CellOccupied)
+        ));
+        assert!(matches!(
+            gs.next_action(&g, Action::Skill(Id::new(13), Id::new(20))),
+            Err(Error::ImpassableCell)
+        ));
+        assert!(matches!(
+            gs.next_action(&g, Action::Skill(Id::new(13), Id::new(62))),
+            Err(Error::CellOutOfMap)
+        ));
+        assert!(matches!(
+            gs.next_action(&g, Action::Skill(Id::new(15), Id::new(62))),
+            Err(Error::CellOutOfMap)
+        ));
+        gs.next_action(&g, Action::Skill(Id::new(13), Id::new(30)))
+            .unwrap();
+        assert_eq!(position(&gs, 1), 30);
+
+        gs.next_action(&g, Action::Skill(Id::new(14), Id::new(30)))
+            .unwrap();
+        assert_eq!(position(&gs, 0), 30);
+        assert_eq!(position(&gs, 1), 3);
+    }
+
+    #[test]
+    fn test_summon() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        let events = gs
+            .next_action(&g, Action::Skill(Id::new(15), Id::new(5)))
+            .unwrap();
+        assert_eq!(
+            events[1],
+            GameEvent::Summoned {
+                owner: Id::new(1),
+                summon: Id::new(2),
+                cell: Id::new(5),
+            }
+        );
+        assert_eq!(gs.characters.get(Id::new(2)).unwrap().team, Id::new(1));
+        assert!(gs.upcoming_turns(&g, 4).contains(&Id::new(2)));
+
+        // the turret plays on its own, attacking the closest enemy, and disappears at the start of
+        // the second turn of its owner after being summoned
+        for _ in 0..3 {
+            gs.next_action(&g, Action::Pass).unwrap();
+        }
+        assert_eq!(health(&gs, 0), 900);
+        assert!(gs.log.contains(&GameEvent::SummonExpired {
+            summon: Id::new(2),
+            owner: Id::new(1),
+            cell: Id::new(5),
+        }));
+        assert!(gs.characters.get(Id::new(2)).is_none());
+        assert!(!gs.upcoming_turns(&g, 4).contains(&Id::new(2)));
+        // its team still learns about it, even though it is gone
+        assert!(gs
+            .visible_events(&g, Some(Id::new(1)), gs.log.clone())
+            .iter()
+            .any(|event| matches!(event, GameEvent::SummonExpired { .. })));
+        // the id of the expired summon is not given to the next one
+        assert_eq!(gs.next_summon, Id::new(3));
+    }
+
+    #[test]
+    fn test_summon_of_dead_owner() {
+        let g = game_definition();
+        let mut gs = game_state_with(&g, &[(0, 0, 0), (1, 4, 1), (0, 6, 1)]);
+
+        // the turret would otherwise keep on attacking forever
+        gs.next_action(&g, Action::Skill(Id::new(15), Id::new(5)))
+            .unwrap();
+        gs.next_action(&g, Action::Skill(Id::new(7), Id::new(4)))
+            .unwrap();
+        assert!(gs.log.contains(&GameEvent::SummonExpired {
+            summon: Id::new(3),
+            owner: Id::new(1),
+            cell: Id::new(5),
+        }));
+        assert!(gs.characters.get(Id::new(3)).is_none());
+        assert!(!gs.upcoming_turns(&g, 4).contains(&Id::new(3)));
+    }
+
+    #[test]
+    fn test_inert_summon() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        gs.next_action(&g, Action::Skill(Id::new(16), Id::new(5)))
+            .unwrap();
+        assert!(!gs.upcoming_turns(&g, 4).contains(&Id::new(2)));
+
+        // summons block cells, and can be targeted
+        assert!(matches!(
+            gs.next_action(&g, Action::Skill(Id::new(16), Id::new(5))),
+            Err(Error::CellOccupied)
+        ));
+        gs.next_action(&g, Action::Skill(Id::new(7), Id::new(5)))
+            .unwrap();
+        assert!(!gs.characters.get(Id::new(2)).unwrap().is_alive());
+
+        // but do not prevent their team from losing
+        gs.next_action(&g, Action::Skill(Id::new(16), Id::new(6)))
+            .unwrap();
+        gs.next_action(&g, Action::Skill(Id::new(7), Id::new(4)))
+            .unwrap();
+        assert_eq!(gs.outcome(), Outcome::Victory(Id::new(0)));
+    }
+
+    #[test]
+    fn test_cell_effect() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        let events = gs
+            .next_action(&g, Action::Skill(Id::new(17), Id::new(0)))
+            .unwrap();
+        assert_eq!(
+            events[1],
+            GameEvent::CellEffectCreated {
+                source: Id::new(1),
+                effect: Id::new(15),
+                cells: vec![Id::new(0), Id::new(1), Id::new(12)],
+            }
+        );
+        // the slow character starts their turn in the fire
+        assert!(events.contains(&GameEvent::CellEffectTriggered {
+            cell: Id::new(0),
+            effect: Id::new(15),
+            character: Id::new(0),
+        }));
+        assert_eq!(health(&gs, 0), 990);
+
+        gs.next_action(&g, Action::Movement(Id::new(13))).unwrap();
+        gs.next_action(&g, Action::Pass).unwrap();
+        gs.next_action(&g, Action::Pass).unwrap();
+        // the DoT keeps ticking, but the slow character is out of the fire (after walking
+        // through another of its cells, refreshing the DoT)
+        assert_eq!(health(&gs, 0), 980);
+        assert_eq!(
+            gs.log
+                .iter()
+                .filter(|e| matches!(e, GameEvent::CellEffectTriggered { .. }))
+                .count(),
+            2
+        );
+
+        // expires at the start of the second turn of the caster after its creation
+        let events = gs.next_action(&g, Action::Pass).unwrap();
+        assert!(events.contains(&GameEvent::CellEffectExpired {
+            cell: Id::new(12),
+            effect: Id::new(15),
+        }));
+        assert!(gs.cell_effects.is_empty());
+    }
+
+    #[test]
+    fn test_trap() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        gs.next_action(&g, Action::Skill(Id::new(18), Id::new(2)))
+            .unwrap();
+        assert!(gs.visible_cell_effects(Id::new(0)).is_empty());
+        assert_eq!(gs.visible_cell_effects(Id::new(1)).len(), 1);
+
+        let events = gs.next_action(&g, Action::Movement(Id::new(2))).unwrap();
+        assert_eq!(
+            events[2],
+            GameEvent::CellEffectTriggered {
+                cell: Id::new(2),
+                effect: Id::new(16),
+                character: Id::new(0),
+            }
+        );
+        assert_eq!(health(&gs, 0), 900);
+        assert!(gs.cell_effects.is_empty());
+    }
+
+    #[test]
+    fn test_move_across_cell_effects() {
+        let mut g = game_definition();
+        let mut gs = game_state(&g);
+
+        // walking across the trap triggers it
+        gs.next_action(&g, Action::Skill(Id::new(18), Id::new(2)))
+            .unwrap();
+        let events = gs.next_action(&g, Action::Movement(Id::new(3))).unwrap();
+        assert_eq!(
+            events[..4],
+            [
+                GameEvent::Moved {
+                    character: Id::new(0),
+                    from: Id::new(0),
+                    to: Id::new(1),
+                },
+                GameEvent::Moved {
+                    character: Id::new(0),
+                    from: Id::new(1),
+                    to: Id::new(2),
+                },
+                GameEvent::CellEffectTriggered {
+                    cell: Id::new(2),
+                    effect: Id::new(16),
+                    character: Id::new(0),
+                },
+                GameEvent::Damaged {
+                    source: Some(Id::new(1)),
+                    target: Id::new(0),
+                    amount: 100,
+                    absorbed: 0,
+                    breakdown: DamageBreakdown {
+                        pure: 100,
+                        ..Default::default()
+                    },
+                },
+            ]
+        );
+        assert_eq!(position(&gs, 0), 3);
+        assert_eq!(health(&gs, 0), 900);
+
+        // so does being pushed across it
+        let mut gs = game_state_with(&g, &[(0, 5, 0), (1, 4, 1)]);
+        gs.next_action(&g, Action::Skill(Id::new(18), Id::new(6)))
+            .unwrap();
+        gs.next_action(&g, Action::Pass).unwrap();
+        gs.next_action(&g, Action::Skill(Id::new(11), Id::new(5)))
+            .unwrap();
+        assert_eq!(position(&gs, 0), 8);
+        assert_eq!(health(&gs, 0), 900);
+
+        // a trap rooting the character stops them on its cell
+        if let EffectKind::CellEffect(cell_effect) =
+            &mut g.effects.get_mut(Id::new(16)).unwrap().kind
+        {
+            cell_effect.effects = vec![Id::new(18)];
+        }
+        let mut gs = game_state(&g);
+        gs.next_action(&g, Action::Skill(Id::new(18), Id::new(2)))
+            .unwrap();
+        gs.next_action(&g, Action::Movement(Id::new(3))).unwrap();
+        assert_eq!(position(&gs, 0), 2);
+    }
+
+    #[test]
+    fn test_area_trap() {
+        let mut g = game_definition();
+        if let EffectKind::CellEffect(cell_effect) =
+            &mut g.effects.get_mut(Id::new(16)).unwrap().kind
+        {
+            cell_effect.range = serde_json::from_value(json!({
+                "min": 0,
+                "max": 1,
+                "kind": "Star",
+                "target": "Anything",
+                "needs_los": false,
+            }))
+            .unwrap();
+        }
+        let mut gs = game_state(&g);
+
+        gs.next_action(&g, Action::Skill(Id::new(18), Id::new(2)))
+            .unwrap();
+        assert_eq!(gs.cell_effects.len(), 4);
+
+        // triggered once, then removed from every cell
+        let events = gs.next_action(&g, Action::Movement(Id::new(1))).unwrap();
+        assert_eq!(health(&gs, 0), 900);
+        assert!(gs.cell_effects.is_empty());
+        for cell in [2, 3, 14] {
+            assert!(events.contains(&GameEvent::CellEffectExpired {
+                cell: Id::new(cell),
+                effect: Id::new(16),
+            }));
+        }
+    }
+
+    #[test]
+    fn test_cell_effect_of_dead_source() {
+        let g = game_definition();
+        let mut gs = game_state_with(&g, &[(0, 0, 0), (1, 4, 1), (0, 5, 1)]);
+
+        gs.next_action(&g, Action::Skill(Id::new(17), Id::new(0)))
+            .unwrap();
+        assert!(!gs.cell_effects.is_empty());
+
+        // the caster will never play again: their effects are removed at the next turn start
+        let events = gs
+            .next_action(&g, Action::Skill(Id::new(7), Id::new(4)))
+            .unwrap();
+        assert_eq!(gs.player_to_play(), Id::new(2));
+        assert!(events.contains(&GameEvent::CellEffectExpired {
+            cell: Id::new(0),
+            effect: Id::new(15),
+        }));
+        assert!(gs.cell_effects.is_empty());
+    }
+
+    #[test]
+    fn test_cooldown() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        gs.next_action(&g, Action::Skill(Id::new(19), Id::new(0)))
+            .unwrap();
+        assert_eq!(
+            gs.skill_cooldown(Id::new(1), Id::new(19)),
+            Some(&SkillCooldown {
+                charges: 0,
+                remaining_turns: 2,
+                uses: 1,
+            })
+        );
+        gs.next_action(&g, Action::Pass).unwrap();
+        assert!(matches!(
+            gs.next_action(&g, Action::Skill(Id::new(19), Id::new(0))),
+            Err(Error::SkillOnCooldown)
+        ));
+        gs.next_action(&g, Action::Pass).unwrap();
+        gs.next_action(&g, Action::Pass).unwrap();
+        gs.next_action(&g, Action::Skill(Id::new(19), Id::new(0)))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_charges_and_uses() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        // two charges, restored every other turn
+        for _ in 0..3 {
+            gs.next_action(&g, Action::Skill(Id::new(20), Id::new(0)))
+                .unwrap();
+            gs.next_action(&g, Action::Pass).unwrap();
+        }
+        assert!(matches!(
+            gs.next_action(&g, Action::Skill(Id::new(20), Id::new(0))),
+            Err(Error::SkillOnCooldown)
+        ));
+
+        gs.next_action(&g, Action::Skill(Id::new(21), Id::new(0)))
+            .unwrap();
+        gs.next_action(&g, Action::Pass).unwrap();
+        assert!(matches!(
+            gs.next_action(&g, Action::Skill(Id::new(21), Id::new(0))),
+            Err(Error::NoUsesLeft)
+        ));
+    }
+
+    #[test]
+    fn test_stun() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        let events = gs
+            .next_action(&g, Action::Skill(Id::new(22), Id::new(0)))
+            .unwrap();
+        assert_eq!(
+            events[2..],
+            [
+                GameEvent::TurnEnded(Id::new(1)),
+                GameEvent::TurnStarted(Id::new(0)),
+                GameEvent::TurnSkipped(Id::new(0)),
+                GameEvent::BuffExpired {
+                    target: Id::new(0),
+                    effect: Id::new(17),
+                },
+                GameEvent::TurnEnded(Id::new(0)),
+                GameEvent::RoundStarted(2),
+                GameEvent::TurnStarted(Id::new(1)),
+            ]
+        );
+        assert_eq!(gs.player_to_play(), Id::new(1));
+    }
+
+    #[test]
+    fn test_root_silence_disarm() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        gs.next_action(&g, Action::Skill(Id::new(23), Id::new(0)))
+            .unwrap();
+        assert!(matches!(
+            gs.next_action(&g, Action::Movement(Id::new(1))),
+            Err(Error::Rooted)
+        ));
+        gs.next_action(&g, Action::Pass).unwrap();
+
+        gs.next_action(&g, Action::Skill(Id::new(24), Id::new(0)))
+            .unwrap();
+        assert!(matches!(
+            gs.next_action(&g, Action::Skill(Id::new(0), Id::new(4))),
+            Err(Error::Silenced)
+        ));
+        gs.next_action(&g, Action::Skill(Id::new(5), Id::new(4)))
+            .unwrap();
+
+        gs.next_action(&g, Action::Skill(Id::new(25), Id::new(0)))
+            .unwrap();
+        assert!(matches!(
+            gs.next_action(&g, Action::Skill(Id::new(5), Id::new(4))),
+            Err(Error::Disarmed)
+        ));
+        gs.next_action(&g, Action::Movement(Id::new(1))).unwrap();
+        gs.next_action(&g, Action::Skill(Id::new(0), Id::new(4)))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_shield() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        gs.next_action(&g, Action::Skill(Id::new(26), Id::new(4)))
+            .unwrap();
+        let events = gs
+            .next_action(&g, Action::Skill(Id::new(0), Id::new(4)))
+            .unwrap();
+        assert!(matches!(
+            events[1],
+            GameEvent::Damaged {
+                amount: 0,
+                absorbed: 100,
+                ..
+            }
+        ));
+        assert_eq!(
+            events[2],
+            GameEvent::Absorbed {
+                source: Some(Id::new(1)),
+                target: Id::new(1),
+                effect: Id::new(21),
+                amount: 100,
+            }
+        );
+
+        // the shield is removed once depleted
+        gs.next_action(&g, Action::Pass).unwrap();
+        let events = gs
+            .next_action(&g, Action::Skill(Id::new(0), Id::new(4)))
+            .unwrap();
+        assert_eq!(
+            events[3],
+            GameEvent::BuffExpired {
+                target: Id::new(1),
+                effect: Id::new(21),
+            }
+        );
+        assert_eq!(health(&gs, 1), 950);
+        assert!(gs.characters.get(Id::new(1)).unwrap().buffs.is_empty());
+    }
+
+    #[test]
+    fn test_typed_shield() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        gs.next_action(&g, Action::Skill(Id::new(27), Id::new(4)))
+            .unwrap();
+        gs.next_action(&g, Action::Skill(Id::new(0), Id::new(4)))
+            .unwrap();
+        assert_eq!(health(&gs, 1), 900);
+
+        gs.next_action(&g, Action::Pass).unwrap();
+        gs.next_action(&g, Action::Skill(Id::new(28), Id::new(4)))
+            .unwrap();
+        assert_eq!(health(&gs, 1), 900);
+        let character = gs.characters.get(Id::new(1)).unwrap();
+        assert_eq!(character.buffs[0].remaining_pool, 400);
+    }
+
+    #[test]
+    fn test_area_of_effect() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        // only allies around the impact point
+        gs.next_action(&g, Action::Skill(Id::new(4), Id::new(3)))
+            .unwrap();
+        assert_eq!(health(&gs, 0), 1000);
+        assert_eq!(health(&gs, 1), 1000);
+
+        gs.next_action(&g, Action::Pass).unwrap();
+        gs.next_action(&g, Action::Skill(Id::new(4), Id::new(1)))
+            .unwrap();
+        assert_eq!(health(&gs, 0), 900);
+        assert_eq!(health(&gs, 1), 1000);
+    }
+
+    #[test]
+    fn test_miss() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        gs.next_action(&g, Action::Skill(Id::new(5), Id::new(0)))
+            .unwrap();
+        assert_eq!(health(&gs, 0), 1000);
+    }
+
+    #[test]
+    fn test_mana() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+        let mana = |gs: &GameState| gs.characters.get(Id::new(1)).unwrap().current_mana;
+
+        gs.next_action(&g, Action::Skill(Id::new(6), Id::new(0)))
+            .unwrap();
+        assert_eq!(mana(&gs), 400);
+
+        // regenerated at the start of the turn
+        gs.next_action(&g, Action::Pass).unwrap();
+        assert_eq!(mana(&gs), 410);
+
+        assert!(matches!(
+            gs.next_action(&g, Action::Skill(Id::new(6), Id::new(0))),
+            Err(Error::NotEnoughMana)
+        ));
+        assert_eq!(health(&gs, 0), 900);
+    }
+
+    #[test]
+    fn test_victory() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+        assert_eq!(gs.outcome(), Outcome::Ongoing);
+
+        gs.next_action(&g, Action::Skill(Id::new(7), Id::new(0)))
+            .unwrap();
+        assert!(!gs.characters.get(Id::new(0)).unwrap().is_alive());
+        assert_eq!(gs.outcome(), Outcome::Victory(Id::new(1)));
+        assert!(matches!(
+            gs.next_action(&g, Action::Pass),
+            Err(Error::GameOver)
+        ));
+    }
+
+    #[test]
+    fn test_dead_skip_turn() {
+        let g = game_definition();
+        let mut gs = game_state_with(&g, &[(0, 0, 0), (0, 1, 0), (1, 4, 1)]);
+
+        gs.next_action(&g, Action::Skill(Id::new(7), Id::new(1)))
+            .unwrap();
+        assert_eq!(gs.player_to_play(), Id::new(0));
+        assert_eq!(gs.outcome(), Outcome::Ongoing);
+
+        // dead characters cannot be targeted anymore
+        assert!(matches!(
+            gs.next_action(&g, Action::Skill(Id::new(0), Id::new(1))),
+            Err(Error::InvalidTarget)
+        ));
+        gs.next_action(&g, Action::Pass).unwrap();
+        assert_eq!(gs.player_to_play(), Id::new(2));
+        assert_eq!(
+            gs.upcoming_turns(&g, 3),
+            vec![Id::new(2), Id::new(0), Id::new(2)]
+        );
+    }
+
+    #[test]
+    fn test_initiative() {
+        let g = game_definition();
+        // the sprinter is more than twice as fast as the slow character
+        let mut gs = game_state_with(&g, &[(0, 0, 0), (2, 4, 1)]);
+        let expected = [1, 1, 0, 1, 1, 0, 1, 1, 0]
+            .iter()
+            .map(|id| Id::new(*id))
+            .collect::<Vec<_>>();
+        assert_eq!(gs.upcoming_turns(&g, 9), expected);
+
+        let mut played = Vec::new();
+        for _ in 0..9 {
+            played.push(gs.player_to_play());
+            gs.next_action(&g, Action::Pass).unwrap();
+        }
+        assert_eq!(played, expected);
+        // a round ends once everybody played at least once
+        assert_eq!(gs.turn, 4);
+    }
+
+    #[test]
+    fn test_effect_target() {
+        let mut g = game_definition();
+        g.effects.get_mut(Id::new(0)).unwrap().target = Some(Target::Enemy);
+        let mut gs = game_state_with(&g, &[(0, 0, 0), (1, 4, 1), (0, 5, 1)]);
+
+        // the skill can target allies, but the damage only applies to enemies
+        let preview = gs
+            .preview_action(&g, Action::Skill(Id::new(0), Id::new(5)))
+            .unwrap();
+        assert!(preview.targets.is_empty());
+        gs.next_action(&g, Action::Skill(Id::new(0), Id::new(5)))
+            .unwrap();
+        assert_eq!(health(&gs, 2), 1000);
+
+        gs.next_action(&g, Action::Skill(Id::new(0), Id::new(4)))
+            .unwrap();
+        assert_eq!(health(&gs, 1), 900);
+    }
+
+    #[test]
+    fn test_target_out_of_map() {
+        let mut g = game_definition();
+        let range = &mut g.skills.get_mut(Id::new(0)).unwrap().range;
+        range.target = Target::Anything;
+        range.needs_los = true;
+        let mut gs = game_state(&g);
+
+        // within range, but below the last row
+        assert!(matches!(
+            gs.next_action(&g, Action::Skill(Id::new(0), Id::new(62))),
+            Err(Error::CellOutOfMap)
+        ));
+        assert_eq!(gs.player_to_play(), Id::new(1));
+    }
+
+    #[test]
+    fn test_movement() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+
+        gs.next_action(&g, Action::Movement(Id::new(2))).unwrap();
+        assert_eq!(gs.characters.get(Id::new(1)).unwrap().position, Id::new(2));
+        assert!(matches!(
+            gs.next_action(&g, Action::Movement(Id::new(3))),
+            Err(Error::AlreadyMoved)
+        ));
+
+        gs.next_action(&g, Action::Pass).unwrap();
+        assert!(matches!(
+            gs.next_action(&g, Action::Movement(Id::new(2))),
+            Err(Error::CellOccupied)
+        ));
+        // slow character has a swiftness of 10
+        assert!(matches!(
+            gs.next_action(&g, Action::Movement(Id::new(59))),
+            Err(Error::MoveCellTooFar)
+        ));
+        gs.next_action(&g, Action::Movement(Id::new(1))).unwrap();
+    }
+
+    #[test]
+    fn test_action_points() {
+        let g = game_definition();
+        let rules = Rules {
+            turns: TurnRules::ActionPoints { action_points: 2 },
+            ..Default::default()
+        };
+        let mut gs = game_state_with_rules(&g, &[(0, 0, 0), (1, 4, 1)], rules);
+        assert_eq!(
+            gs.turn_state,
+            TurnState::Points {
+                action_points: 2,
+                movement_points: 11
+            }
+        );
+
+        // moves can be split, and interleaved with skills
+        gs.next_action(&g, Action::Movement(Id::new(7))).unwrap();
+        gs.next_action(&g, Action::Skill(Id::new(0), Id::new(0)))
+            .unwrap();
+        gs.next_action(&g, Action::Movement(Id::new(9))).unwrap();
+        gs.next_action(&g, Action::Skill(Id::new(0), Id::new(0)))
+            .unwrap();
+        assert_eq!(position(&gs, 1), 9);
+        assert_eq!(health(&gs, 0), 800);
+        assert_eq!(
+            gs.turn_state,
+            TurnState::Points {
+                action_points: 0,
+                movement_points: 6
+            }
+        );
+
+        assert!(matches!(
+            gs.next_action(&g, Action::Skill(Id::new(0), Id::new(0))),
+            Err(Error::NotEnoughActionPoints)
+        ));
+        assert!(matches!(
+            gs.next_action(&g, Action::Movement(Id::new(48))),
+            Err(Error::MoveCellTooFar)
+        ));
+        assert_eq!(gs.player_to_play(), Id::new(1));
+
+        gs.next_action(&g, Action::EndTurn).unwrap();
+        assert_eq!(gs.player_to_play(), Id::new(0));
+        assert_eq!(
+            gs.turn_state,
+            TurnState::Points {
+                action_points: 2,
+                movement_points: 10
+            }
+        );
+    }
+
+    #[test]
+    fn test_fog_of_war() {
+        let mut g = game_definition();
+        for class in [0, 1] {
+            g.classes.get_mut(Id::new(class)).unwrap().vision = 3;
+        }
+        let mut gs = game_state(&g);
+        let characters = |view: &GameState| {
+            let mut ids = view.characters.ids();
+            ids.sort_by_key(|id| id.raw());
+            ids.into_iter().map(|id| id.raw()).collect::<Vec<_>>()
+        };
+
+        // casting the trap ends the turn of the character
+        let events = gs
+            .next_action(&g, Action::Skill(Id::new(18), Id::new(2)))
+            .unwrap();
+        let view = gs.view_for(&g, Some(Id::new(0)));
+        assert_eq!(characters(&view), vec![0]);
+        assert_eq!(view.current, Some(Id::new(0)));
+        assert!(view.cell_effects.is_empty());
+        assert!(view.log.is_empty());
+        assert_eq!(
+            gs.visible_events(&g, Some(Id::new(0)), events.clone()),
+            vec![GameEvent::TurnStarted(Id::new(0))]
+        );
+        let view = gs.view_for(&g, Some(Id::new(1)));
+        assert_eq!(characters(&view), vec![1]);
+        // the playing character is out of sight
+        assert_eq!(view.current, None);
+        assert_eq!(view.cell_effects.len(), 1);
+        assert_eq!(
+            gs.visible_events(&g, Some(Id::new(1)), events.clone()),
+            events[..3]
+        );
+        assert!(gs.view_for(&g, None).characters.ids().is_empty());
+        assert!(gs.visible_events(&g, None, events).is_empty());
+
+        gs.next_action(&g, Action::Pass).unwrap();
+        let events = gs.next_action(&g, Action::Movement(Id::new(3))).unwrap();
+        // the character moved in sight
+        assert!(gs
+            .visible_events(&g, Some(Id::new(0)), events)
+            .contains(&GameEvent::Moved {
+                character: Id::new(1),
+                from: Id::new(4),
+                to: Id::new(3),
+            }));
+        let view = gs.view_for(&g, Some(Id::new(0)));
+        assert_eq!(characters(&view), vec![0, 1]);
+        // the trap is now in sight, but still hidden
+        assert!(view.cell_effects.is_empty());
+    }
+
+    #[test]
+    fn test_hidden_attacker() {
+        let mut g = game_definition();
+        for class in [0, 1] {
+            g.classes.get_mut(Id::new(class)).unwrap().vision = 3;
+        }
+        let mut gs = game_state(&g);
+
+        // the damage taken is reported, without revealing who dealt it
+        let events = gs
+            .next_action(&g, Action::Skill(Id::new(0), Id::new(0)))
+            .unwrap();
+        let damaged = |source: Option<usize>| GameEvent::Damaged {
+            source: source.map(Id::new),
+            target: Id::new(0),
+            amount: 100,
+            absorbed: 0,
+            breakdown: DamageBreakdown {
+                pure: 100,
+                ..Default::default()
+            },
+        };
+        assert!(events.contains(&damaged(Some(1))));
+        let visible = gs.visible_events(&g, Some(Id::new(0)), events);
+        assert!(visible.contains(&damaged(None)));
+        assert!(!visible
+            .iter()
+            .any(|event| matches!(event, GameEvent::SkillCast { .. })));
+    }
+
+    #[test]
+    fn test_king_of_the_hill() {
+        let mut g = game_definition();
+        // the mode comes from the map
+        let map = g.maps.get_mut(Id::new(0)).unwrap();
+        map.objectives.hill = vec![Id::new(0)];
+        map.mode = Some(GameMode::KingOfTheHill { rounds: 2 });
+        let mut gs = game_state(&g);
+
+        let mut events = Vec::new();
+        while gs.outcome() == Outcome::Ongoing {
+            events.extend(gs.next_action(&g, Action::Pass).unwrap());
+        }
+        assert_eq!(gs.outcome(), Outcome::Victory(Id::new(0)));
+        assert_eq!(gs.turn, 2);
+        let scores = events
+            .into_iter()
+            .filter(|event| matches!(event, GameEvent::Scored { .. }))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            scores,
+            vec![
+                GameEvent::Scored {
+                    team: Id::new(0),
+                    score: 1
+                },
+                GameEvent::Scored {
+                    team: Id::new(0),
+                    score: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_king_of_the_hill_streak() {
+        let mut g = game_definition();
+        let map = g.maps.get_mut(Id::new(0)).unwrap();
+        map.objectives.hill = vec![Id::new(0)];
+        map.mode = Some(GameMode::KingOfTheHill { rounds: 2 });
+        let mut gs = game_state(&g);
+
+        // the slow character leaves the hill for a round, then comes back
+        let mut actions = vec![
+            Action::Pass,
+            Action::Movement(Id::new(1)),
+            Action::Pass,
+            Action::Movement(Id::new(0)),
+        ]
+        .into_iter();
+        let mut events = Vec::new();
+        while gs.outcome() == Outcome::Ongoing {
+            let action = match gs.current {
+                Some(id) if id == Id::new(0) => actions.next().unwrap_or(Action::Pass),
+                _ => Action::Pass,
+            };
+            events.extend(gs.next_action(&g, action).unwrap());
+        }
+        assert_eq!(gs.outcome(), Outcome::Victory(Id::new(0)));
+        let scores = events
+            .into_iter()
+            .filter_map(|event| match event {
+                GameEvent::Scored { score, .. } => Some(score),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(scores, vec![1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_capture_points() {
+        let mut g = game_definition();
+        let map = g.maps.get_mut(Id::new(0)).unwrap();
+        map.objectives.capture_zones = vec![vec![Id::new(4)], vec![Id::new(5), Id::new(6)]];
+        // the mode chosen at creation takes precedence over the one of the map
+        map.mode = Some(GameMode::Deathmatch);
+        let rules = Rules {
+            mode: Some(GameMode::CapturePoints {
+                capture_rounds: 2,
+                score: 1,
+            }),
+            ..Default::default()
+        };
+        let mut gs = game_state_with_rules(&g, &[(0, 0, 0), (1, 4, 1)], rules);
+
+        let mut events = Vec::new();
+        while gs.turn < 2 {
+            events.extend(gs.next_action(&g, Action::Pass).unwrap());
+        }
+        assert_eq!(
+            gs.captures[0],
+            Capture {
+                owner: None,
+                progress: Some((Id::new(1), 1))
+            }
+        );
+        assert_eq!(gs.captures[1], Capture::default());
+
+        while gs.outcome() == Outcome::Ongoing {
+            events.extend(gs.next_action(&g, Action::Pass).unwrap());
+        }
+        assert_eq!(gs.outcome(), Outcome::Victory(Id::new(1)));
+        assert!(events.contains(&GameEvent::ZoneCaptured {
+            zone: 0,
+            team: Id::new(1)
+        }));
+        assert_eq!(gs.scores[&Id::new(1)], 1);
+    }
+
+    #[test]
+    fn test_escort() {
+        let mut g = game_definition();
+        g.maps.get_mut(Id::new(0)).unwrap().objectives.exits = vec![Id::new(6)];
+        let rules = Rules {
+            mode: Some(GameMode::Escort { team: Id::new(1) }),
+            ..Default::default()
+        };
+
+        // the escort is designated when the characters are created
+        let character = |class: usize, cell: usize, team: usize, escort: bool| {
+            let mut character = Character::new(
+                Id::new(class),
+                Id::new(cell),
+                g.classes.get(Id::new(class)).unwrap(),
+                "name",
+                Id::new(team),
+            );
+            character.escort = escort;
+            character
+        };
+        let mut builder = CharacterMapBuilder::new(&g, Id::new(0), 1, &rules);
+        assert!(matches!(
+            builder.add(character(0, 0, 0, true)),
+            Err(Error::InvalidEscort)
+        ));
+        assert!(matches!(
+            builder.add(character(1, 4, 1, false)),
+            Err(Error::MissingEscort)
+        ));
+        builder.add(character(0, 0, 0, false)).unwrap();
+        builder.add(character(1, 4, 1, true)).unwrap();
+        assert!(builder.can_build());
+
+        let escorted = |characters_def: &[(usize, usize, usize)], escort: usize| {
+            let mut characters = characters(&g, characters_def);
+            characters.get_mut(Id::new(escort)).unwrap().escort = true;
+            GameState::new(&g, characters, Id::new(0), rules.clone(), 0)
+        };
+        let characters_def = [(0, 0, 0), (1, 4, 1), (0, 5, 1)];
+
+        let mut gs = escorted(&characters_def, 1);
+        let events = gs.next_action(&g, Action::Movement(Id::new(6))).unwrap();
+        assert!(events.contains(&GameEvent::ExitReached(Id::new(1))));
+        assert_eq!(
+            events.last(),
+            Some(&GameEvent::GameOver(Outcome::Victory(Id::new(1))))
+        );
+
+        // only the escort completes the objective
+        let mut gs = escorted(&characters_def, 2);
+        gs.next_action(&g, Action::Movement(Id::new(6))).unwrap();
+        assert_eq!(gs.outcome(), Outcome::Ongoing);
+
+        // whichever way they get there
+        let mut gs = escorted(&characters_def, 1);
+        gs.next_action(&g, Action::Skill(Id::new(13), Id::new(6)))
+            .unwrap();
+        assert_eq!(gs.outcome(), Outcome::Victory(Id::new(1)));
+        let gs = escorted(&[(0, 0, 0), (1, 6, 1), (0, 5, 1)], 1);
+        assert!(gs.log.contains(&GameEvent::ExitReached(Id::new(1))));
+        assert_eq!(gs.outcome(), Outcome::Victory(Id::new(1)));
+
+        // the escorting team loses as soon as the escort dies
+        let mut gs = escorted(&characters_def, 1);
+        gs.next_action(&g, Action::Pass).unwrap();
+        gs.next_action(&g, Action::Skill(Id::new(7), Id::new(4)))
+            .unwrap();
+        assert_eq!(gs.outcome(), Outcome::Victory(Id::new(0)));
+    }
+
+    #[test]
+    fn test_event_log() {
+        let g = game_definition();
+        let mut gs = game_state(&g);
+        assert_eq!(
+            gs.log,
+            vec![
+                GameEvent::RoundStarted(1),
+                GameEvent::TurnStarted(Id::new(1))
+            ]
+        );
+
+        let events = gs
+            .next_action(&g, Action::Skill(Id::new(1), Id::new(0)))
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                GameEvent::SkillCast {
+                    caster: Id::new(1),
+                    skill: Id::new(1),
+                    target: Id::new(0),
+                },
+                GameEvent::BuffApplied {
+                    source: Some(Id::new(1)),
+                    target: Id::new(0),
+                    effect: Id::new(1),
+                },
+                GameEvent::TurnEnded(Id::new(1)),
+                GameEvent::TurnStarted(Id::new(0)),
+                GameEvent::Damaged {
+                    source: Some(Id::new(1)),
+                    target: Id::new(0),
+                    amount: 10,
+                    absorbed: 0,
+                    breakdown: DamageBreakdown {
+                        pure: 10,
+                        ..Default::default()
+                    },
+                },
+            ]
+        );
+        assert_eq!(gs.log.len(), 7);
+
+        let events = gs.next_action(&g, Action::Skill(Id::new(7), Id::new(4)));
+        assert_eq!(
+            events.unwrap()[1..],
+            [
+                GameEvent::Damaged {
+                    source: Some(Id::new(0)),
+                    target: Id::new(1),
+                    amount: 1000,
+                    absorbed: 0,
+                    breakdown: DamageBreakdown {
+                        pure: 1000,
+                        ..Default::default()
+                    },
+                },
+                GameEvent::Died(Id::new(1)),
+                GameEvent::TurnEnded(Id::new(0)),
+                GameEvent::GameOver(Outcome::Victory(Id::new(0))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_preview() {
+        let g = game_definition();
+        let gs = game_state(&g);
+
+        let preview = gs
+            .preview_action(&g, Action::Skill(Id::new(5), Id::new(0)))
+            .unwrap();
+        assert_eq!(
+            preview,
+            ActionPreview {
+                mana_after: 1000,
+                cells: vec![Id::new(0)],
+                targets: vec![TargetPreview {
+                    character: Id::new(0),
+                    hit_chance: 0.0,
+                    min_damage: 0,
+                    max_damage: 100,
+                    damage: DamageBreakdown {
+                        pure: 100,
+                        ..Default::default()
+                    },
+                    heal: 0,
+                    buffs: Vec::new(),
+                }],
+            }
+        );
+
+        let preview = gs
+            .preview_action(&g, Action::Skill(Id::new(4), Id::new(1)))
+            .unwrap();
+        assert_eq!(preview.cells.len(), 4);
+        assert_eq!(preview.targets[0].min_damage, 100);
+
+        let preview = gs.preview_action(&g, Action::Movement(Id::new(2))).unwrap();
+        assert_eq!(preview.cells, vec![Id::new(4), Id::new(3), Id::new(2)]);
+
+        assert!(matches!(
+            gs.preview_action(&g, Action::Skill(Id::new(6), Id::new(24))),
+            Err(Error::InvalidTarget)
+        ));
+
+        // nothing changed
+        assert_eq!(health(&gs, 0), 1000);
+        assert_eq!(gs.log.len(), 2);
     }
 }
@@ -0,0 +1,58 @@
+use crate::game::character::Stats;
+use serde::{Deserialize, Serialize};
+
+/// What happens to the healing exceeding the maximum health of the target
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overheal {
+    // the excess is lost
+    #[default]
+    Discard,
+    // the excess is converted into a shield absorbing incoming damage
+    Shield,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Heal {
+    #[serde(default)]
+    flat: i32,
+    /// Percentage of the maximum health of the target
+    #[serde(default)]
+    perc_max_health: f32,
+
+    /// Scales with the healer's intelligence
+    #[serde(default)]
+    perc_modif_magical: f32,
+    /// Scales with the healer's strength
+    #[serde(default)]
+    perc_modif_physical: f32,
+
+    #[serde(default)]
+    pub overheal: Overheal,
+}
+
+impl Heal {
+    pub(crate) fn new(
+        flat: i32,
+        perc_max_health: f32,
+        perc_modif_magical: f32,
+        perc_modif_physical: f32,
+    ) -> Heal {
+        Heal {
+            flat,
+            perc_max_health,
+            perc_modif_magical,
+            perc_modif_physical,
+            overheal: Overheal::default(),
+        }
+    }
+
+    /// Amount of health restored, before clamping to the maximum health of the target
+    pub fn compute_heal(&self, healer: &Stats, target: &Stats) -> i32 {
+        let heal = self.flat
+            + ((self.perc_max_health * target.health as f32) as i32)
+            + ((self.perc_modif_magical * healer.intelligence as f32) as i32)
+            + ((self.perc_modif_physical * healer.strength as f32) as i32);
+        heal.max(0)
+    }
+}
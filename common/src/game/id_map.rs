@@ -79,6 +79,23 @@ impl<T> IdMap<T> {
     pub fn ids(&self) -> Vec<Id<T>> {
         self.0.keys().copied().collect()
     }
+
+    /// Removes the elements for which the predicate returns false, the others keep their ids
+    pub fn retain<F: FnMut(&Id<T>, &mut T) -> bool>(&mut self, f: F) {
+        self.0.retain(f);
+    }
+
+    /// Inserts an element with the given id, replacing the existing one (if any)
+    pub fn insert(&mut self, id: Id<T>, t: T) -> Option<T> {
+        self.0.insert(id, t)
+    }
+
+    /// Adds an element after the map was built, with an id greater than all the existing ones
+    pub fn add(&mut self, t: T) -> Id<T> {
+        let id = Id::new(self.0.keys().map(|id| id.raw() + 1).max().unwrap_or(0));
+        self.0.insert(id, t);
+        id
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -96,6 +113,13 @@ impl<T> IdMapBuilder<T> {
         id
     }
 
+    /// Adds an element with an externally defined id (e.g. from the data files), returning the
+    /// element previously stored with that id if any
+    pub fn insert(&mut self, id: Id<T>, t: T) -> Option<T> {
+        self.1 = std::cmp::max(self.1, id.raw() + 1);
+        self.0.insert(id, t)
+    }
+
     pub fn build(self) -> IdMap<T> {
         IdMap(self.0)
     }
@@ -1,9 +1,11 @@
+use crate::game::effect::{Range, RangeKind};
 use crate::game::error::Error;
 use crate::game::id_map::Id;
+use crate::game::mode::{GameMode, Objectives};
 use log::debug;
 use serde::{Deserialize, Serialize};
 use std::cmp::{Ord, Ordering, Reverse};
-use std::collections::{BinaryHeap, HashSet};
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::ops::Index;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -11,7 +13,7 @@ pub struct Team(pub String, pub Vec<Id<Cell>>);
 
 impl Id<Cell> {
     fn invalid() -> Id<Cell> {
-        Id::new(std::usize::MAX)
+        Id::new(usize::MAX)
     }
 }
 
@@ -30,15 +32,58 @@ pub struct Cell {
     attribute: CellAttibute,
 }
 
+impl Cell {
+    /// Whether characters can stand on (and move through) the cell
+    pub fn is_walkable(&self) -> bool {
+        !matches!(self.attribute, CellAttibute::Wall | CellAttibute::Hole)
+    }
+
+    /// Characters ending up in a hole (e.g. when pushed) fall to their death
+    pub fn is_hole(&self) -> bool {
+        matches!(self.attribute, CellAttibute::Hole)
+    }
+}
+
+/// Cells occupied by other characters, from the point of view of the one moving
+#[derive(Debug, Default)]
+pub struct Occupied {
+    /// can neither be moved through nor onto
+    pub blocking: HashSet<Id<Cell>>,
+    /// can be moved through, but not onto (e.g. allies)
+    pub passable: HashSet<Id<Cell>>,
+}
+
+impl Occupied {
+    pub fn contains(&self, c: Id<Cell>) -> bool {
+        self.blocking.contains(&c) || self.passable.contains(&c)
+    }
+}
+
+fn default_water_cost() -> u32 {
+    1
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GameMap {
     pub name: String,
+    /// Additional movement cost to enter a water cell
+    #[serde(default = "default_water_cost")]
+    pub water_cost: u32,
     pub data: Vec<Cell>,
     pub width: usize,
     pub height: usize,
     pub teams: Vec<Team>,
+    #[serde(default)]
+    pub objectives: Objectives,
+    /// Mode used when none is chosen at the creation of the game
+    #[serde(default)]
+    pub mode: Option<GameMode>,
 }
 
+// Heights used for the line of sight computation, relative to the height of the cell
+const WALL_HEIGHT: i32 = 2;
+const CHARACTER_HEIGHT: i32 = 1;
+
 // Used internally for the A* path computation
 #[derive(Eq)]
 struct Node {
@@ -85,6 +130,10 @@ impl GameMap {
             }
         }
 
+        if self.objectives.cells().any(|c| !self.is_valid_cell(*c)) {
+            return Err(Error::InvalidObjectiveCell);
+        }
+
         debug!(
             "Map named `{}` checked properly (size {}x{})",
             self.name, self.width, self.height
@@ -108,6 +157,70 @@ impl GameMap {
         (x as i32, y as i32)
     }
 
+    /// Cell at the given offset from `c`, if it is within the map
+    pub fn offset(&self, c: Id<Cell>, (dx, dy): (i32, i32)) -> Option<Id<Cell>> {
+        let (x, y) = self.id_to_xy_i32(c);
+        let (x, y) = (x + dx, y + dy);
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            None
+        } else {
+            Some(self.xy_to_id(x as usize, y as usize))
+        }
+    }
+
+    /// Unit step going from `from` towards `to`, along the main axis between both cells (or
+    /// diagonally if both axes are equal). None if both cells are the same.
+    pub fn direction(&self, from: Id<Cell>, to: Id<Cell>) -> Option<(i32, i32)> {
+        let (fx, fy) = self.id_to_xy_i32(from);
+        let (tx, ty) = self.id_to_xy_i32(to);
+        let (dx, dy) = (tx - fx, ty - fy);
+        match dx.abs().cmp(&dy.abs()) {
+            _ if dx == 0 && dy == 0 => None,
+            Ordering::Greater => Some((dx.signum(), 0)),
+            Ordering::Less => Some((0, dy.signum())),
+            Ordering::Equal => Some((dx.signum(), dy.signum())),
+        }
+    }
+
+    /// Checks if `to` is part of the shape of the given range, centered on `from`. Directional
+    /// shapes are oriented along `facing` (see `direction`), if any.
+    pub fn in_shape(
+        &self,
+        from: Id<Cell>,
+        to: Id<Cell>,
+        range: &Range,
+        facing: Option<(i32, i32)>,
+    ) -> bool {
+        let (fx, fy) = self.id_to_xy_i32(from);
+        let (tx, ty) = self.id_to_xy_i32(to);
+        in_shape((tx - fx, ty - fy), range, facing)
+    }
+
+    /// Every cell of the map being part of the shape of the given range, centered on `from`
+    pub fn shape(
+        &self,
+        from: Id<Cell>,
+        range: &Range,
+        facing: Option<(i32, i32)>,
+    ) -> Vec<Id<Cell>> {
+        (0..self.data.len())
+            .map(Id::new)
+            .filter(|c| self.in_shape(from, *c, range, facing))
+            .collect()
+    }
+
+    /// Cells seen by a character standing on `from`: within the given distance, and in line of
+    /// sight (characters do not block the view)
+    pub fn visible_cells(&self, from: Id<Cell>, vision: u32) -> Vec<Id<Cell>> {
+        (0..self.data.len())
+            .map(Id::new)
+            .filter(|c| {
+                self.distance(from, *c) <= vision
+                    && self.has_line_of_sight(from, *c, &HashSet::new())
+            })
+            .collect()
+    }
+
     fn surrounding_cells(&self, c: Id<Cell>) -> [Id<Cell>; 4] {
         let (x, y) = self.id_to_xy(c);
 
@@ -148,48 +261,261 @@ impl GameMap {
         ((ax as i32 - bx as i32).abs() + (ay as i32 - by as i32).abs()) as u32
     }
 
-    pub fn can_move_to(&self, start: Id<Cell>, end: Id<Cell>, swiftness: i32) -> bool {
+    /// Height of the top of the obstacle standing on the cell (if any)
+    fn obstacle_height(&self, c: Id<Cell>, blockers: &HashSet<Id<Cell>>) -> i32 {
+        let cell = &self[c];
+        match cell.attribute {
+            CellAttibute::Wall => cell.height + WALL_HEIGHT,
+            _ if blockers.contains(&c) => cell.height + CHARACTER_HEIGHT,
+            _ => cell.height,
+        }
+    }
+
+    /// Checks if `b` can be seen from `a`, by walking along the line between the center of both
+    /// cells. The line starts and ends at the height of a character standing on each cell, and
+    /// is blocked by any obstacle (ground, wall, or character standing on one of the `blockers`
+    /// cells) reaching it. When the line passes exactly between two cells, it is only blocked if
+    /// both are.
+    pub fn has_line_of_sight(
+        &self,
+        a: Id<Cell>,
+        b: Id<Cell>,
+        blockers: &HashSet<Id<Cell>>,
+    ) -> bool {
+        let (ax, ay) = self.id_to_xy_i32(a);
+        let (bx, by) = self.id_to_xy_i32(b);
+        let (dx, dy) = (bx - ax, by - ay);
+        let n = std::cmp::max(dx.abs(), dy.abs());
+
+        let eye_a = (self[a].height + CHARACTER_HEIGHT) as f32;
+        let eye_b = (self[b].height + CHARACTER_HEIGHT) as f32;
+
+        // coordinates are scaled by n to avoid dealing with floats; returns one or two candidate
+        // coordinates for the given step
+        let candidates = |origin: i32, delta: i32, k: i32| {
+            let scaled = origin * n + delta * k;
+            let (floor, remainder) = (scaled / n, scaled % n);
+            match (2 * remainder).cmp(&n) {
+                Ordering::Less => (floor, None),
+                Ordering::Greater => (floor + 1, None),
+                Ordering::Equal => (floor, Some(floor + 1)),
+            }
+        };
+
+        (1..n).all(|k| {
+            let line_height = eye_a + (eye_b - eye_a) * k as f32 / n as f32;
+            let is_blocking = |x: i32, y: i32| {
+                let c = self.xy_to_id(x as usize, y as usize);
+                self.obstacle_height(c, blockers) as f32 >= line_height
+            };
+
+            let (x, other_x) = candidates(ax, dx, k);
+            let (y, other_y) = candidates(ay, dy, k);
+            let blocked = match (other_x, other_y) {
+                (None, None) => is_blocking(x, y),
+                (Some(other_x), None) => is_blocking(x, y) && is_blocking(other_x, y),
+                (None, Some(other_y)) => is_blocking(x, y) && is_blocking(x, other_y),
+                (Some(_), Some(_)) => {
+                    unreachable!("The line always goes through cell centers on its main axis")
+                }
+            };
+            !blocked
+        })
+    }
+
+    /// Cost of moving from one cell to an adjacent one: climbing costs more, as does getting in
+    /// water
+    fn move_cost(&self, from: Id<Cell>, to: Id<Cell>) -> u32 {
+        let (from, to) = (&self[from], &self[to]);
+        let water_cost = match to.attribute {
+            CellAttibute::Water => self.water_cost,
+            _ => 0,
+        };
+        std::cmp::max(0, to.height - from.height) as u32 + 1 + water_cost
+    }
+
+    /// Cells which can be moved into from the given cell
+    fn walkable_neighbors<'a>(
+        &'a self,
+        c: Id<Cell>,
+        occupied: &'a Occupied,
+    ) -> impl Iterator<Item = Id<Cell>> + 'a {
+        self.surrounding_cells(c)
+            .into_iter()
+            .filter(move |neighbor| {
+                self.is_valid_cell(*neighbor)
+                    && self[*neighbor].is_walkable()
+                    && !occupied.blocking.contains(neighbor)
+            })
+    }
+
+    /// Shortest path (A*) from `start` to `end`, both included, costing at most `budget`
+    pub fn find_path(
+        &self,
+        start: Id<Cell>,
+        end: Id<Cell>,
+        budget: u32,
+        occupied: &Occupied,
+    ) -> Option<Vec<Id<Cell>>> {
+        if !self.is_valid_cell(end) || !self[end].is_walkable() || occupied.contains(end) {
+            return None;
+        }
+
+        let mut costs = HashMap::new();
+        let mut came_from = HashMap::new();
+        let mut visited = HashSet::new();
         let mut nodes = BinaryHeap::new();
+        costs.insert(start, 0);
         nodes.push(Reverse(Node {
             real_cost: 0,
             heuristic: self.distance(start, end),
             curr_cell: start,
         }));
 
-        loop {
-            let n = if let Some(n) = nodes.pop() {
-                n.0
-            } else {
-                // No path possible (should never happen though...)
-                return false;
-            };
-
+        while let Some(Reverse(n)) = nodes.pop() {
             // our two exit conditions (arrived, or too long)
-            if swiftness < n.cost() as i32 {
-                return false;
+            if budget < n.cost() {
+                return None;
             } else if end == n.curr_cell {
-                return true;
+                let mut path = vec![end];
+                while let Some(previous) = came_from.get(path.last().unwrap()) {
+                    path.push(*previous);
+                }
+                path.reverse();
+                return Some(path);
             }
 
-            let prev_cell = &self[n.curr_cell];
-            for neighbor in self.surrounding_cells(n.curr_cell).iter() {
-                let neighbor = *neighbor;
-                if !self.is_valid_cell(neighbor) {
+            // the heuristic is consistent, so the first visit is always the cheapest one
+            if !visited.insert(n.curr_cell) {
+                continue;
+            }
+
+            for neighbor in self.walkable_neighbors(n.curr_cell, occupied) {
+                let real_cost = n.real_cost + self.move_cost(n.curr_cell, neighbor);
+                if costs.get(&neighbor).is_some_and(|cost| *cost <= real_cost) {
                     continue;
                 }
 
-                let next_cell = &self[neighbor];
-
-                let additional_cost =
-                    std::cmp::max(0, next_cell.height - prev_cell.height) as u32 + 1;
-
+                costs.insert(neighbor, real_cost);
+                came_from.insert(neighbor, n.curr_cell);
                 nodes.push(Reverse(Node {
-                    real_cost: n.real_cost + additional_cost,
+                    real_cost,
                     heuristic: self.distance(neighbor, end),
                     curr_cell: neighbor,
                 }));
             }
         }
+
+        // No path possible
+        None
+    }
+
+    /// Every cell which can be moved onto from `start` (included) for at most `budget`, with the
+    /// cost to get there (Dijkstra)
+    pub fn reachable_cells(
+        &self,
+        start: Id<Cell>,
+        budget: u32,
+        occupied: &Occupied,
+    ) -> HashMap<Id<Cell>, u32> {
+        let mut costs = HashMap::new();
+        let mut nodes = BinaryHeap::new();
+        nodes.push(Reverse(Node {
+            real_cost: 0,
+            heuristic: 0,
+            curr_cell: start,
+        }));
+
+        while let Some(Reverse(n)) = nodes.pop() {
+            if costs.contains_key(&n.curr_cell) {
+                continue;
+            }
+            costs.insert(n.curr_cell, n.real_cost);
+
+            for neighbor in self.walkable_neighbors(n.curr_cell, occupied) {
+                let real_cost = n.real_cost + self.move_cost(n.curr_cell, neighbor);
+                if real_cost <= budget && !costs.contains_key(&neighbor) {
+                    nodes.push(Reverse(Node {
+                        real_cost,
+                        heuristic: 0,
+                        curr_cell: neighbor,
+                    }));
+                }
+            }
+        }
+
+        // cells occupied by allies can be moved through, but not onto
+        costs.retain(|c, _| !occupied.passable.contains(c));
+        costs
+    }
+
+    pub fn can_move_to(
+        &self,
+        start: Id<Cell>,
+        end: Id<Cell>,
+        swiftness: i32,
+        occupied: &Occupied,
+    ) -> bool {
+        swiftness >= 0
+            && self
+                .find_path(start, end, swiftness as u32, occupied)
+                .is_some()
+    }
+}
+
+/// Checks if the offset `(dx, dy)` from the center of a shape is part of it
+fn in_shape((dx, dy): (i32, i32), range: &Range, facing: Option<(i32, i32)>) -> bool {
+    let within = |d: i32| d >= range.min as i32 && d <= range.max as i32;
+    let facings = match facing {
+        Some(facing) => vec![facing],
+        None => vec![(1, 0), (0, 1), (-1, 0), (0, -1)],
+    };
+    // distance travelled along the facing direction, and distance from the line it follows.
+    // For diagonal facings, the former is the L1 distance of the cell.
+    let project = |(fx, fy): (i32, i32)| (dx * fx + dy * fy, (dx * fy - dy * fx).abs());
+
+    match &range.kind {
+        RangeKind::Star => within(dx.abs() + dy.abs()),
+        RangeKind::Cross => (dx == 0 || dy == 0) && within(dx.abs() + dy.abs()),
+        RangeKind::Square => within(std::cmp::max(dx.abs(), dy.abs())),
+        RangeKind::Line => facings.into_iter().any(|(fx, fy)| {
+            let (along, lateral) = project((fx, fy));
+            // diagonal steps move by 2 along the facing direction
+            let step = fx * fx + fy * fy;
+            lateral == 0 && along >= 0 && along % step == 0 && within(along / step)
+        }),
+        RangeKind::Diagonal => dx.abs() == dy.abs() && within(dx.abs()),
+        RangeKind::Cone => facings.into_iter().any(|facing| {
+            let (along, lateral) = project(facing);
+            lateral < std::cmp::max(along, 1) && within(along)
+        }),
+        RangeKind::Ring => within(f64::from(dx * dx + dy * dy).sqrt().round() as i32),
+        RangeKind::Mask(rows) => {
+            // masks can only be rotated by quarter turns, diagonal facings are approximated by
+            // their horizontal component
+            let (fx, fy) = match facing {
+                None => (0, -1),
+                Some((fx, fy)) if fx != 0 && fy != 0 => (fx, 0),
+                Some(facing) => facing,
+            };
+            // coordinates within the (upward facing) pattern
+            let px = dy * fx - dx * fy;
+            let py = -(dx * fx + dy * fy);
+
+            let width = rows
+                .iter()
+                .map(|row| row.chars().count())
+                .max()
+                .unwrap_or(0) as i32;
+            let height = rows.len() as i32;
+            let (x, y) = (px + width / 2, py + height / 2);
+            x >= 0
+                && y >= 0
+                && rows
+                    .get(y as usize)
+                    .and_then(|row| row.chars().nth(x as usize))
+                    == Some('X')
+        }
     }
 }
 
@@ -203,8 +529,29 @@ impl Index<Id<Cell>> for GameMap {
 
 #[cfg(test)]
 mod test {
-    use super::{Cell, CellAttibute, Error, GameMap, Team};
+    use super::{Cell, CellAttibute, Error, GameMap, Occupied, Team};
+    use crate::game::effect::{Range, RangeKind, Target};
     use crate::game::id_map::Id;
+    use std::collections::HashSet;
+
+    fn plain_map(width: usize, height: usize) -> GameMap {
+        GameMap {
+            name: "".to_owned(),
+            water_cost: 1,
+            width,
+            height,
+            data: vec![
+                Cell {
+                    height: 0,
+                    attribute: CellAttibute::None,
+                };
+                width * height
+            ],
+            teams: Default::default(),
+            objectives: Default::default(),
+            mode: None,
+        }
+    }
 
     #[test]
     fn test_xy_to_id() {
@@ -212,10 +559,13 @@ mod test {
         let h = 6;
         let map = GameMap {
             name: "".to_owned(),
+            water_cost: 1,
             width: w,
             height: h,
             data: Vec::new(),
             teams: Default::default(),
+            objectives: Default::default(),
+            mode: None,
         };
 
         let x = 1;
@@ -232,10 +582,13 @@ mod test {
         let h = 6;
         let map = GameMap {
             name: "".to_owned(),
+            water_cost: 1,
             width: w,
             height: h,
             data: Vec::new(),
             teams: Default::default(),
+            objectives: Default::default(),
+            mode: None,
         };
 
         let cell_id = Id::new(22);
@@ -251,10 +604,13 @@ mod test {
         let h = 6;
         let map = GameMap {
             name: "".to_owned(),
+            water_cost: 1,
             width: w,
             height: h,
             data: Vec::new(),
             teams: Default::default(),
+            objectives: Default::default(),
+            mode: None,
         };
 
         let cell_id = Id::new(22);
@@ -269,6 +625,7 @@ mod test {
         let height = 5;
         let map = GameMap {
             name: "".to_owned(),
+            water_cost: 1,
             width,
             height,
             data: vec![
@@ -279,6 +636,8 @@ mod test {
                 25
             ],
             teams: Default::default(),
+            objectives: Default::default(),
+            mode: None,
         };
 
         assert!(matches!(map.check_validity(), Ok(())));
@@ -298,6 +657,7 @@ mod test {
 
         let map = GameMap {
             name: "".to_owned(),
+            water_cost: 1,
             width,
             height,
             data: vec![
@@ -308,6 +668,8 @@ mod test {
                 25
             ],
             teams,
+            objectives: Default::default(),
+            mode: None,
         };
 
         assert!(matches!(map.check_validity(), Ok(())));
@@ -319,6 +681,7 @@ mod test {
         let height = 2;
         let map = GameMap {
             name: "".to_owned(),
+            water_cost: 1,
             width,
             height,
             data: vec![
@@ -329,6 +692,8 @@ mod test {
                 20
             ],
             teams: Default::default(),
+            objectives: Default::default(),
+            mode: None,
         };
 
         assert!(matches!(map.check_validity(), Err(Error::InvalidMapSize)));
@@ -348,6 +713,7 @@ mod test {
 
         let map = GameMap {
             name: "".to_owned(),
+            water_cost: 1,
             width,
             height,
             data: vec![
@@ -358,6 +724,8 @@ mod test {
                 25
             ],
             teams,
+            objectives: Default::default(),
+            mode: None,
         };
 
         assert!(matches!(
@@ -372,6 +740,7 @@ mod test {
         let height = 5;
         let map = GameMap {
             name: "".to_owned(),
+            water_cost: 1,
             width,
             height,
             data: vec![
@@ -382,11 +751,255 @@ mod test {
                 25
             ],
             teams: Default::default(),
+            objectives: Default::default(),
+            mode: None,
         };
 
         let start = Id::new(0);
         let end = Id::new(4);
-        assert!(map.can_move_to(start, end, 4));
-        assert!(!map.can_move_to(start, end, 3));
+        let occupied = Default::default();
+        assert!(map.can_move_to(start, end, 4, &occupied));
+        assert!(!map.can_move_to(start, end, 3, &occupied));
+    }
+
+    #[test]
+    fn test_los_wall() {
+        let mut map = plain_map(5, 5);
+        let blockers = HashSet::new();
+        map.data[2].attribute = CellAttibute::Wall;
+
+        assert!(!map.has_line_of_sight(Id::new(0), Id::new(4), &blockers));
+        assert!(map.has_line_of_sight(Id::new(0), Id::new(2), &blockers));
+        assert!(map.has_line_of_sight(Id::new(5), Id::new(9), &blockers));
+
+        // the line goes right between (2, 0) and (2, 1)
+        assert!(map.has_line_of_sight(Id::new(0), Id::new(9), &blockers));
+        map.data[7].attribute = CellAttibute::Wall;
+        assert!(!map.has_line_of_sight(Id::new(0), Id::new(9), &blockers));
+    }
+
+    #[test]
+    fn test_los_height() {
+        let mut map = plain_map(5, 1);
+        let blockers = HashSet::new();
+        map.data[2].height = 1;
+
+        assert!(!map.has_line_of_sight(Id::new(0), Id::new(4), &blockers));
+        map.data[0].height = 2;
+        assert!(map.has_line_of_sight(Id::new(0), Id::new(4), &blockers));
+
+        map.data[2].attribute = CellAttibute::Wall;
+        assert!(!map.has_line_of_sight(Id::new(0), Id::new(4), &blockers));
+        map.data[0].height = 6;
+        assert!(map.has_line_of_sight(Id::new(0), Id::new(4), &blockers));
+    }
+
+    #[test]
+    fn test_los_characters() {
+        let map = plain_map(5, 1);
+        let blockers = [Id::new(2)].into_iter().collect();
+
+        assert!(!map.has_line_of_sight(Id::new(0), Id::new(4), &blockers));
+        assert!(map.has_line_of_sight(Id::new(0), Id::new(2), &blockers));
+        assert!(map.has_line_of_sight(Id::new(0), Id::new(4), &HashSet::new()));
+    }
+
+    #[test]
+    fn test_a_star_obstacles() {
+        let mut map = plain_map(5, 2);
+        let occupied = Default::default();
+        map.data[2].attribute = CellAttibute::Wall;
+
+        // going around the wall
+        assert!(!map.can_move_to(Id::new(0), Id::new(4), 5, &occupied));
+        assert!(map.can_move_to(Id::new(0), Id::new(4), 6, &occupied));
+        assert!(!map.can_move_to(Id::new(0), Id::new(2), 10, &occupied));
+
+        map.data[7].attribute = CellAttibute::Hole;
+        assert!(!map.can_move_to(Id::new(0), Id::new(4), 10, &occupied));
+
+        map.data[7].attribute = CellAttibute::Water;
+        assert!(!map.can_move_to(Id::new(0), Id::new(4), 6, &occupied));
+        assert!(map.can_move_to(Id::new(0), Id::new(4), 7, &occupied));
+    }
+
+    #[test]
+    fn test_a_star_occupied() {
+        let map = plain_map(5, 1);
+        let mut occupied = Occupied::default();
+        occupied.passable.insert(Id::new(2));
+
+        assert!(map.can_move_to(Id::new(0), Id::new(4), 4, &occupied));
+        assert!(!map.can_move_to(Id::new(0), Id::new(2), 4, &occupied));
+
+        occupied.blocking.insert(Id::new(3));
+        assert!(!map.can_move_to(Id::new(0), Id::new(4), 10, &occupied));
+    }
+
+    #[test]
+    fn test_find_path() {
+        let mut map = plain_map(5, 2);
+        let occupied = Default::default();
+        map.data[2].attribute = CellAttibute::Wall;
+        map.data[3].attribute = CellAttibute::Wall;
+        map.data[5].attribute = CellAttibute::Hole;
+
+        let path = map
+            .find_path(Id::new(0), Id::new(4), 10, &occupied)
+            .unwrap();
+        let path = path.into_iter().map(Id::raw).collect::<Vec<_>>();
+        assert_eq!(path, vec![0, 1, 6, 7, 8, 9, 4]);
+        assert!(map
+            .find_path(Id::new(0), Id::new(4), 5, &occupied)
+            .is_none());
+        assert_eq!(
+            map.find_path(Id::new(0), Id::new(0), 0, &occupied),
+            Some(vec![Id::new(0)])
+        );
+    }
+
+    #[test]
+    fn test_find_path_unreachable() {
+        // the target is enclosed by walls: every cell of the map needs to be visited
+        let mut map = plain_map(50, 50);
+        let occupied = Default::default();
+        for c in [1, 50, 51] {
+            map.data[c].attribute = CellAttibute::Wall;
+        }
+
+        assert!(map
+            .find_path(Id::new(2499), Id::new(0), 10000, &occupied)
+            .is_none());
+    }
+
+    #[test]
+    fn test_reachable_cells() {
+        let mut map = plain_map(3, 3);
+        let mut occupied = Occupied::default();
+        occupied.passable.insert(Id::new(1));
+        map.data[3].attribute = CellAttibute::Water;
+
+        let reachable = map.reachable_cells(Id::new(0), 2, &occupied);
+        let mut cells = reachable.keys().map(|c| c.raw()).collect::<Vec<_>>();
+        cells.sort();
+        assert_eq!(cells, vec![0, 2, 3, 4]);
+        assert_eq!(reachable[&Id::new(3)], 2);
+        assert_eq!(reachable[&Id::new(4)], 2);
+    }
+
+    #[test]
+    fn test_offset() {
+        let map = plain_map(4, 3);
+        assert_eq!(map.offset(Id::new(5), (1, 1)), Some(Id::new(10)));
+        assert_eq!(map.offset(Id::new(5), (-1, -1)), Some(Id::new(0)));
+        assert_eq!(map.offset(Id::new(3), (1, 0)), None);
+        assert_eq!(map.offset(Id::new(8), (0, 1)), None);
+    }
+
+    #[test]
+    fn test_direction() {
+        let map = plain_map(5, 5);
+        assert_eq!(map.direction(Id::new(12), Id::new(12)), None);
+        assert_eq!(map.direction(Id::new(12), Id::new(14)), Some((1, 0)));
+        assert_eq!(map.direction(Id::new(12), Id::new(2)), Some((0, -1)));
+        // main axis wins
+        assert_eq!(map.direction(Id::new(12), Id::new(5)), Some((-1, 0)));
+        assert_eq!(map.direction(Id::new(12), Id::new(24)), Some((1, 1)));
+    }
+
+    #[test]
+    fn test_validity_objectives_nok() {
+        let mut map = plain_map(5, 5);
+        map.objectives.hill = vec![Id::new(24)];
+        assert!(matches!(map.check_validity(), Ok(())));
+
+        map.objectives.exits = vec![Id::new(25)];
+        assert!(matches!(
+            map.check_validity(),
+            Err(Error::InvalidObjectiveCell)
+        ));
+    }
+
+    #[test]
+    fn test_visible_cells() {
+        let mut map = plain_map(5, 2);
+        map.data[2].attribute = CellAttibute::Wall;
+
+        let mut cells = map
+            .visible_cells(Id::new(0), 3)
+            .into_iter()
+            .map(|c| c.raw())
+            .collect::<Vec<_>>();
+        cells.sort();
+        // the wall hides the cells behind it, on the first row
+        assert_eq!(cells, vec![0, 1, 2, 5, 6, 7]);
+    }
+
+    fn shape(kind: RangeKind, min: u32, max: u32, facing: Option<(i32, i32)>) -> Vec<usize> {
+        let map = plain_map(5, 5);
+        let range = Range {
+            min,
+            max,
+            kind,
+            target: Target::Anything,
+            needs_los: false,
+            blocked_by_characters: false,
+        };
+        let mut cells = map
+            .shape(Id::new(12), &range, facing)
+            .into_iter()
+            .map(|c| c.raw())
+            .collect::<Vec<_>>();
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn test_shape_square() {
+        assert_eq!(
+            shape(RangeKind::Square, 1, 1, None),
+            vec![6, 7, 8, 11, 13, 16, 17, 18]
+        );
+    }
+
+    #[test]
+    fn test_shape_line() {
+        assert_eq!(shape(RangeKind::Line, 1, 2, Some((1, 0))), vec![13, 14]);
+        assert_eq!(shape(RangeKind::Line, 1, 2, Some((1, 1))), vec![18, 24]);
+        // no facing: every main direction
+        assert_eq!(
+            shape(RangeKind::Line, 1, 2, None),
+            vec![2, 7, 10, 11, 13, 14, 17, 22]
+        );
+    }
+
+    #[test]
+    fn test_shape_diagonal() {
+        assert_eq!(shape(RangeKind::Diagonal, 1, 1, None), vec![6, 8, 16, 18]);
+    }
+
+    #[test]
+    fn test_shape_cone() {
+        assert_eq!(
+            shape(RangeKind::Cone, 1, 2, Some((0, -1))),
+            vec![1, 2, 3, 7]
+        );
+        assert_eq!(shape(RangeKind::Cone, 0, 1, Some((1, 0))), vec![12, 13]);
+    }
+
+    #[test]
+    fn test_shape_ring() {
+        assert_eq!(
+            shape(RangeKind::Ring, 2, 2, None),
+            vec![1, 2, 3, 5, 9, 10, 14, 15, 19, 21, 22, 23]
+        );
+    }
+
+    #[test]
+    fn test_shape_mask() {
+        let mask = || RangeKind::Mask(vec![".X.".to_owned(), "XX.".to_owned(), "...".to_owned()]);
+        assert_eq!(shape(mask(), 0, 0, None), vec![7, 11, 12]);
+        assert_eq!(shape(mask(), 0, 0, Some((1, 0))), vec![7, 12, 13]);
+        assert_eq!(shape(mask(), 0, 0, Some((0, 1))), vec![12, 13, 17]);
     }
 }
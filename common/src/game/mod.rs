@@ -4,26 +4,46 @@ pub use error::Error;
 mod id_map;
 pub use id_map::{Id, IdMap, IdMapBuilder};
 
+mod cell_effect;
+pub use cell_effect::{CellEffect, CellEffectInstance, Trigger};
+
 mod character;
-pub use character::{Character, CharacterMapBuilder};
+pub use character::{Absorption, BuffInstance, Character, CharacterMapBuilder, Stats};
+
+mod event;
+pub use event::GameEvent;
 
 mod game_definition;
 pub use game_definition::GameDefinition;
 
 mod game_state;
-pub use game_state::{Action, GameState};
+pub use game_state::{
+    Action, ActionPreview, GameState, Outcome, Rules, TargetPreview, TurnRules, TurnState,
+};
 
 mod class;
 pub use class::Class;
 
 mod damage;
-pub use damage::Damage;
+pub use damage::{Damage, DamageBreakdown};
 
 mod effect;
-pub use effect::Effect;
+pub use effect::{Effect, ShieldKind, Status};
+
+mod heal;
+pub use heal::{Heal, Overheal};
 
 mod map;
-pub use map::{Cell, GameMap, Team};
+pub use map::{Cell, GameMap, Occupied, Team};
+
+mod mode;
+pub use mode::{Capture, GameMode, Objectives};
+
+mod rng;
+pub use rng::Rng;
+
+mod summon;
+pub use summon::{Behaviour, Summon, Summoned};
 
 mod skill;
-pub use skill::Skill;
+pub use skill::{Skill, SkillCooldown, SkillKind};
@@ -0,0 +1,83 @@
+use crate::game::id_map::Id;
+use crate::game::map::{Cell, Team};
+use serde::{Deserialize, Serialize};
+
+/// How a game is won. Whatever the mode, a team wins once it is the last one with characters
+/// alive.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameMode {
+    /// Teams only win by eliminating the others
+    #[default]
+    Deathmatch,
+    /// A team captures a zone by being the only one standing on it for `capture_rounds` rounds
+    /// in a row. Every round, each team scores a point per zone it owns, and the first one to
+    /// reach `score` wins.
+    CapturePoints { capture_rounds: u32, score: u32 },
+    /// The first team to stand alone on the hill at the end of `rounds` rounds in a row wins.
+    /// The score of a team is the number of rounds it has held the hill so far, and drops back
+    /// to 0 as soon as it does not hold it at the end of a round.
+    KingOfTheHill { rounds: u32 },
+    /// The escort of the given team (see `Character::escort`) must reach one of the exits for the
+    /// team to win, and the team loses if they die
+    Escort { team: Id<Team> },
+}
+
+/// Cells of the map the game modes rely on
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Objectives {
+    /// Cells making each zone of the `CapturePoints` mode
+    #[serde(default)]
+    pub capture_zones: Vec<Vec<Id<Cell>>>,
+    #[serde(default)]
+    pub hill: Vec<Id<Cell>>,
+    /// Cells the escorted character must reach
+    #[serde(default)]
+    pub exits: Vec<Id<Cell>>,
+}
+
+impl Objectives {
+    pub fn cells(&self) -> impl Iterator<Item = &Id<Cell>> {
+        self.capture_zones
+            .iter()
+            .flatten()
+            .chain(self.hill.iter())
+            .chain(self.exits.iter())
+    }
+}
+
+/// State of a capture zone
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capture {
+    pub owner: Option<Id<Team>>,
+    /// Team currently capturing the zone, with the number of rounds it held it so far
+    pub progress: Option<(Id<Team>, u32)>,
+}
+
+impl Capture {
+    /// Updates the zone at the end of a round, given the team standing alone on it (if any).
+    /// Returns true if the team just captured it.
+    pub fn update(&mut self, holder: Option<Id<Team>>, capture_rounds: u32) -> bool {
+        let team = match holder {
+            // contested or empty zones keep their progress
+            None => return false,
+            Some(team) if self.owner == Some(team) => {
+                self.progress = None;
+                return false;
+            }
+            Some(team) => team,
+        };
+
+        let rounds = match self.progress {
+            Some((capturing, rounds)) if capturing == team => rounds + 1,
+            _ => 1,
+        };
+        if rounds >= capture_rounds {
+            self.owner = Some(team);
+            self.progress = None;
+            true
+        } else {
+            self.progress = Some((team, rounds));
+            false
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// Small deterministic PRNG (SplitMix64). Its state is part of the game state, so that clients
+/// and replays get the exact same results as the server.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        // f32 only has 24 bits of precision
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Returns true with the given probability
+    pub fn roll(&mut self, chance: f32) -> bool {
+        self.next_f32() < chance
+    }
+}
@@ -1,14 +1,123 @@
+use crate::game::character::Stats;
 use crate::game::effect::{Effect, Range};
 use crate::game::id_map::Id;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
+// Hit chance of a physical skill when the attacker's dexterity equals the defender's swiftness
+const BASE_HIT_CHANCE: f32 = 0.9;
+const MIN_HIT_CHANCE: f32 = 0.05;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkillKind {
+    // can be dodged, depending on the attacker's dexterity and the defender's swiftness
+    Physical,
+    // always hits, regardless of its precision
+    Magical,
+}
+
+fn default_action_points() -> u32 {
+    1
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Skill {
     pub name: String,
+    pub kind: SkillKind,
 
     pub cost: i32,
+    /// Action points spent when casting the skill, with the action point rules
+    #[serde(default = "default_action_points")]
+    pub action_points: u32,
     pub range: Range,
+    /// Multiplier applied to the hit chance of physical skills, defaults to 1
     pub precision: Option<f32>,
     pub effects: HashSet<Id<Effect>>,
+
+    /// Number of turns of the caster during which the skill cannot be used again e.g. 1 means
+    /// every other turn. With charges, this is the time needed to restore each of them
+    #[serde(default)]
+    pub cooldown: u32,
+    /// Number of times the skill can be used in a row before waiting for the cooldown, defaults
+    /// to 1
+    #[serde(default)]
+    pub charges: Option<u32>,
+    #[serde(default)]
+    pub uses_per_game: Option<u32>,
+}
+
+/// Cooldown and charges of a skill for a given character, once they used it at least once
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SkillCooldown {
+    pub charges: u32,
+    /// Turns of the caster left before the next charge is restored (at the start of the turn it
+    /// reaches 0), 0 if every charge is available
+    pub remaining_turns: u32,
+    pub uses: u32,
+}
+
+impl Skill {
+    pub fn max_charges(&self) -> u32 {
+        self.charges.unwrap_or(1)
+    }
+
+    /// Whether all the charges were used (None if the skill was never used)
+    pub fn is_on_cooldown(&self, cooldown: Option<&SkillCooldown>) -> bool {
+        cooldown.is_some_and(|cooldown| cooldown.charges == 0)
+    }
+
+    pub fn has_uses_left(&self, cooldown: Option<&SkillCooldown>) -> bool {
+        let uses = cooldown.map_or(0, |cooldown| cooldown.uses);
+        self.uses_per_game.is_none_or(|max| uses < max)
+    }
+
+    /// Consumes a charge (if the skill has a cooldown)
+    pub fn use_charge(&self, cooldown: Option<SkillCooldown>) -> SkillCooldown {
+        let mut cooldown = cooldown.unwrap_or(SkillCooldown {
+            charges: self.max_charges(),
+            remaining_turns: 0,
+            uses: 0,
+        });
+        cooldown.uses += 1;
+        if self.cooldown > 0 {
+            cooldown.charges = cooldown.charges.saturating_sub(1);
+            if cooldown.remaining_turns == 0 {
+                // the turn of the cast does not count
+                cooldown.remaining_turns = self.cooldown + 1;
+            }
+        }
+        cooldown
+    }
+
+    /// Called at the start of each turn of the caster, restores a charge once the cooldown is over
+    pub fn tick_cooldown(&self, cooldown: &mut SkillCooldown) {
+        if cooldown.remaining_turns == 0 {
+            return;
+        }
+        cooldown.remaining_turns -= 1;
+        if cooldown.remaining_turns == 0 {
+            cooldown.charges += 1;
+            if cooldown.charges < self.max_charges() {
+                cooldown.remaining_turns = self.cooldown + 1;
+            }
+        }
+    }
+
+    pub fn hit_chance(&self, attacker: &Stats, defender: &Stats) -> f32 {
+        let precision = self.precision.unwrap_or(1.0);
+        match self.kind {
+            SkillKind::Magical => 1.0,
+            SkillKind::Physical => {
+                let dexterity = attacker.dexterity as f32;
+                let swiftness = defender.swiftness as f32;
+                let balance = if dexterity + swiftness > 0.0 {
+                    (dexterity - swiftness) / (dexterity + swiftness)
+                } else {
+                    0.0
+                };
+                precision * (BASE_HIT_CHANCE + balance).clamp(MIN_HIT_CHANCE, 1.0)
+            }
+        }
+        .clamp(0.0, 1.0)
+    }
 }
@@ -0,0 +1,34 @@
+use crate::game::character::Character;
+use crate::game::class::Class;
+use crate::game::id_map::Id;
+use serde::{Deserialize, Serialize};
+
+/// How a summoned unit plays its turns
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Behaviour {
+    // never plays, and does not appear in the turn order e.g. a wall of ice
+    #[default]
+    Inert,
+    // casts the first skill it can on the closest enemy, and passes otherwise e.g. a turret
+    Attack,
+}
+
+/// Effect spawning a unit on the target cell, which must be free
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Summon {
+    /// Stat block (and skills) of the unit, see `Class::summon`
+    pub class: Id<Class>,
+    #[serde(default)]
+    pub behaviour: Behaviour,
+    /// Number of turns of the summoner after which the unit disappears, None means it stays until
+    /// killed
+    pub lifetime: Option<u32>,
+}
+
+/// Data attached to the characters spawned by a `Summon` effect
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Summoned {
+    pub owner: Id<Character>,
+    pub behaviour: Behaviour,
+    pub remaining_turns: Option<u32>,
+}
@@ -1,15 +1,24 @@
-use crate::game::{Action, Cell, Character, Class, GameMap, GameState, Id, Team};
+use crate::game::{
+    Action, Cell, Character, Class, GameEvent, GameMap, GameState, Id, Outcome, Rules, Team,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct WireAction(pub Action);
 
+/// Events resulting from an action
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WireEvents(pub Vec<GameEvent>);
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct WireNewCharRequest {
     pub name: String,
     pub class: Id<Class>,
     pub team: Id<Team>,
     pub position: Id<Cell>,
+    /// Required for exactly one character of the escorted team with the `Escort` mode
+    #[serde(default)]
+    pub escort: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -26,11 +35,22 @@ pub struct WireCreatedChar(pub String, pub Id<Character>);
 pub struct WireNewGameRequest {
     pub map: Id<GameMap>,
     pub team_size: usize,
+    #[serde(default)]
+    pub rules: Rules,
+}
+
+/// What a player knows of a running game. The outcome is computed on the full state, as the view
+/// alone lacks the characters out of sight.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WireGameView {
+    pub game_state: GameState,
+    pub outcome: Outcome,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub enum WireGetGame {
-    Running(GameState),
+    Running(WireGameView),
+    Finished(GameState),
     BeingCreated(WireCreatedGame),
     None,
 }
@@ -81,6 +81,17 @@ impl Endpoint {
             .json()
             .unwrap()
     }
+
+    /// Game as seen by the given character's team
+    pub fn game_state_for<S: AsRef<str>>(&self, game_id: S, char_id: S) -> WireGetGame {
+        let path = format!("{}/{}", game_id.as_ref(), char_id.as_ref());
+        self.client
+            .get(self.url.join(&path).unwrap())
+            .send()
+            .unwrap()
+            .json()
+            .unwrap()
+    }
 }
 
 impl fmt::Debug for Endpoint {
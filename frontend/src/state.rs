@@ -1,5 +1,5 @@
 use crate::api::Endpoint;
-use common::game::{Character, Class, GameDefinition, GameMap, GameState, Id, Team};
+use common::game::{Character, Class, GameDefinition, GameMap, GameState, Id, Outcome, Team};
 use common::io::{
     WireCreatedChar, WireCreatedGame, WireGetGame, WireNewCharRequest, WireNewGameRequest,
 };
@@ -158,7 +158,7 @@ impl GlobalState {
             team_index: 0,
             position_index: 0,
 
-            classes: game_definition.classes.ids(),
+            classes: game_definition.playable_classes(),
             teams: game_definition
                 .maps
                 .get(created_game.map)
@@ -224,8 +224,12 @@ impl State for CreateOrJoinState {
                             WireGetGame::BeingCreated(created_game) => {
                                 GlobalState::join_game(created_game, game_definition)
                             }
-                            WireGetGame::Running(game_state) => {
-                                let map = game_definition.maps.get(game_state.map).unwrap();
+                            WireGetGame::Finished(_) => {
+                                info!("Game with id {} is already over", s.login);
+                                GlobalState::CreateOrJoin(CreateOrJoinState::Join(s))
+                            }
+                            WireGetGame::Running(view) => {
+                                let map = game_definition.maps.get(view.game_state.map).unwrap();
                                 let create_character = CreateCharacterState {
                                     name: String::new(),
                                     step: CreateCharacterStep::Team,
@@ -233,14 +237,14 @@ impl State for CreateOrJoinState {
                                     team_index: 0,
                                     position_index: 0,
 
-                                    classes: game_definition.classes.ids(),
+                                    classes: game_definition.playable_classes(),
                                     teams: map
                                         .teams
                                         .iter()
                                         .enumerate()
                                         .map(|(index, _)| Id::new(index))
                                         .collect(),
-                                    map: game_state.map,
+                                    map: view.game_state.map,
                                     game_id: s.login,
                                 };
                                 GlobalState::CreateCharacter(create_character)
@@ -306,6 +310,7 @@ impl State for SelectMapState {
                 let request = WireNewGameRequest {
                     map: map_id,
                     team_size: 2,
+                    rules: Default::default(),
                 };
                 // TODO this can fail :)
                 let created_game = endpoint.create_game(request);
@@ -381,6 +386,7 @@ impl CreateCharacterState {
                         class,
                         team,
                         position,
+                        escort: false,
                     },
                 );
                 GlobalState::WaitForGameCreation(WaitForGameCreationState {
@@ -521,21 +527,28 @@ impl State for WaitForGameCreationState {
         endpoint: &Endpoint,
         _event: Event,
     ) -> GlobalState {
-        let game = endpoint.game_state(&self.game_id);
-        match game {
-            WireGetGame::Running(game_state) => GlobalState::PlayGame(PlayGameState {
-                //cell: (),
-                is_our_turn: *game_state.turn_order.last().unwrap() == self.id,
-                game_state,
-                map: self.map,
-                game_id: self.game_id,
-                char_id: self.char_id,
-                id: self.id,
-            }),
-            WireGetGame::BeingCreated(_) => GlobalState::WaitForGameCreation(self),
+        let game = endpoint.game_state_for(&self.game_id, &self.char_id);
+        let (game_state, is_our_turn) = match game {
+            // the view lacks the characters out of sight, hence the outcome sent along
+            WireGetGame::Running(view) => {
+                let is_our_turn =
+                    view.outcome == Outcome::Ongoing && view.game_state.current == Some(self.id);
+                (view.game_state, is_our_turn)
+            }
+            WireGetGame::Finished(game_state) => (game_state, false),
+            WireGetGame::BeingCreated(_) => return GlobalState::WaitForGameCreation(self),
             WireGetGame::None => panic!(
                 "Need to handle this! No game being created, but state is waiting for a game..."
             ),
-        }
+        };
+        GlobalState::PlayGame(PlayGameState {
+            //cell: (),
+            game_state,
+            map: self.map,
+            game_id: self.game_id,
+            char_id: self.char_id,
+            id: self.id,
+            is_our_turn,
+        })
     }
 }
@@ -124,6 +124,8 @@ impl<'a> Display for FormatMapSelection<'a> {
 pub(super) struct FormatMap<'a>(
     pub(super) &'a GameMap,
     pub(super) Option<&'a IdMap<Character>>,
+    /// Cells with a (visible) cell effect on them
+    pub(super) &'a [Id<Cell>],
 );
 impl<'a> FormatMap<'a> {
     fn character(&self, id: Id<Cell>) -> Option<&'a Character> {
@@ -158,20 +160,30 @@ impl<'a> Widget for FormatMap<'a> {
             let cell_rect = Rect::new(x * cell_width, y * cell_height, cell_width, cell_height);
             // get the character in the cell (if any)
             let character = self.character(id);
+            let has_cell_effect = self.2.contains(&id);
 
-            FormatCell(cell, character, corners).render(cell_rect, buf);
+            FormatCell(cell, character, corners, has_cell_effect).render(cell_rect, buf);
 
             id = Id::new(id.raw() + 1);
         }
     }
 }
 
-struct FormatCell<'a>(&'a Cell, Option<&'a Character>, CellCorner);
+struct FormatCell<'a>(&'a Cell, Option<&'a Character>, CellCorner, bool);
 impl<'a> Widget for FormatCell<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let _cell = self.0;
         let _character = self.1;
         let corners = &self.2;
+        let has_cell_effect = self.3;
+
+        if has_cell_effect {
+            for x in (area.left() + 1)..area.right() {
+                for y in (area.top() + 1)..area.bottom() {
+                    buf.get_mut(x, y).set_char('░');
+                }
+            }
+        }
 
         for x in 1..area.width {
             let x = x + area.left();
@@ -245,14 +245,16 @@ impl<'a, 'c, B: tui::backend::Backend> Renderer<'a, 'c, B> {
             }
         };
         let map = self.g.maps.get(map).unwrap();
-        self.f.render_widget(FormatMap(map, None), self.chunks[0]);
+        self.f
+            .render_widget(FormatMap(map, None, &[]), self.chunks[0]);
     }
 
     fn select_map(self, s: &SelectMapState) {
         let map_ids = &s.map_ids;
         let curr_id = s.curr_id;
         let map = self.g.maps.get(*map_ids.get(curr_id).unwrap()).unwrap();
-        self.f.render_widget(FormatMap(map, None), self.chunks[0]);
+        self.f
+            .render_widget(FormatMap(map, None, &[]), self.chunks[0]);
 
         let text = [
             Text::styled(
@@ -283,11 +285,46 @@ impl<'a, 'c, B: tui::backend::Backend> Renderer<'a, 'c, B> {
 
     fn play_game(self, s: &PlayGameState) {
         let map = self.g.maps.get(s.game_state.map).unwrap();
-        self.f.render_widget(FormatMap(map, None), self.chunks[0]);
+        let team = s.game_state.characters.get(s.id).unwrap().team;
+        let cell_effects = s
+            .game_state
+            .visible_cell_effects(team)
+            .iter()
+            .map(|(cell, _)| *cell)
+            .collect::<Vec<_>>();
+        self.f
+            .render_widget(FormatMap(map, None, &cell_effects), self.chunks[0]);
+
+        let character = s.game_state.characters.get(s.id).unwrap();
+        let mut skills = self
+            .g
+            .class_to_skills
+            .get(&character.class)
+            .cloned()
+            .unwrap_or_default();
+        skills.sort_by_key(|id| id.raw());
+        let mut text = Text::default();
+        for skill_id in skills {
+            let skill = self.g.skills.get(skill_id).unwrap();
+            let cooldown = s.game_state.skill_cooldown(s.id, skill_id);
+            let status = match cooldown {
+                Some(cooldown) if cooldown.charges == 0 => {
+                    format!("ready in {} turn(s)", cooldown.remaining_turns)
+                }
+                Some(cooldown) if skill.max_charges() > 1 => {
+                    format!("{} / {} charges", cooldown.charges, skill.max_charges())
+                }
+                _ => "ready".to_owned(),
+            };
+            text.extend(Text::raw(format!("    {}: {}", skill.name, status)));
+        }
+
         self.f.render_widget(
-            Block::default()
-                .title("PLAYING THE GAME ASODUHASUOB")
-                .borders(Borders::ALL),
+            Paragraph::new(text).block(
+                Block::default()
+                    .title("PLAYING THE GAME ASODUHASUOB")
+                    .borders(Borders::ALL),
+            ),
             self.chunks[1],
         );
     }